@@ -7,60 +7,16 @@ use std::cmp::*;
 #[allow(unused_imports)]
 use std::collections::*;
 
-use rand::{thread_rng, Rng};
-use std::time::SystemTime;
+use heuristic_core::anneal::{Annealer, Annealing, Budget, Linear};
+use heuristic_core::geom::Coord;
+use heuristic_core::params::Params;
+use heuristic_core::rng::Rng;
+use heuristic_core::timer::Timer;
 
 const TIMEOUT_MS: u128 = 5_000;
+const TIMEOUT_MARGIN_MS: u128 = 4_100; // 焼きなましを切り上げるまでに残しておく時間
 const SIDE: usize = 10_000;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct Coord {
-    x: isize,
-    y: isize,
-}
-
-#[allow(dead_code)]
-impl Coord {
-    fn new(p: (isize, isize)) -> Coord {
-        Coord { x: p.0, y: p.1 }
-    }
-
-    fn in_field(pos: Self) -> bool {
-        (0 <= pos.x && pos.x <= SIDE as isize) && (0 <= pos.y && pos.y <= SIDE as isize)
-    }
-
-    // ペアへの変換
-    fn to_pair(&self) -> (isize, isize) {
-        (self.x as isize, self.y as isize)
-    }
-
-    // マンハッタン距離
-    fn distance(&self, that: Coord) -> isize {
-        let dist_x = max(self.x, that.x) - min(self.x, that.x);
-        let dist_y = max(self.y, that.y) - min(self.y, that.y);
-        dist_x + dist_y
-    }
-
-    // 四則演算
-    fn plus(&self, that: &Coord) -> Self {
-        Coord::new((self.x + that.x, self.y + that.y))
-    }
-    fn minus(&self, that: &Coord) -> Self {
-        Coord::new((self.x - that.x, self.y - that.y))
-    }
-
-    fn mk_4dir(&self) -> Vec<Self> {
-        let (ix, iy) = self.to_pair();
-        let delta = [(-1, 0), (1, 0), (0, -1), (0, 1)];
-
-        delta
-            .iter()
-            .map(|&(dx, dy)| Coord::new((ix + dx, iy + dy)))
-            .filter(|&pos| Coord::in_field(pos))
-            .collect()
-    }
-}
-
 struct Request {
     spot: Coord,
     area: usize,
@@ -156,9 +112,7 @@ impl Rectangle {
         let request = &input.requests[id];
         if self.does_include_point(&request.spot) {
             let area = self.calc_area() as f64;
-            let score = 1.0
-                - (1.0 - (request.area as f64).min(area) / (request.area as f64).max(area)).powi(2);
-            score
+            1.0 - (1.0 - (request.area as f64).min(area) / (request.area as f64).max(area)).powi(2)
         } else {
             0.0
         }
@@ -180,46 +134,46 @@ impl Rectangle {
 
 // 一方向に1広げることを表現する
 enum Expander {
-    ToLeft,
-    ToUp,
-    ToRight,
-    ToDown,
+    Left,
+    Up,
+    Right,
+    Down,
 }
 
 impl Expander {
     fn pushed_delta(&self) -> Rectangle {
         match self {
-            Self::ToLeft => Rectangle::new(Coord::new((0, 0)), Coord::new((-1, 0))),
-            Self::ToUp => Rectangle::new(Coord::new((0, 0)), Coord::new((0, -1))),
-            Self::ToRight => Rectangle::new(Coord::new((1, 0)), Coord::new((0, 0))),
-            Self::ToDown => Rectangle::new(Coord::new((0, 1)), Coord::new((0, 0))),
+            Self::Left => Rectangle::new(Coord::new((0, 0)), Coord::new((-1, 0))),
+            Self::Up => Rectangle::new(Coord::new((0, 0)), Coord::new((0, -1))),
+            Self::Right => Rectangle::new(Coord::new((1, 0)), Coord::new((0, 0))),
+            Self::Down => Rectangle::new(Coord::new((0, 1)), Coord::new((0, 0))),
         }
     }
 
     fn push_delta(&self) -> Rectangle {
         match self {
-            Self::ToLeft => Rectangle::new(Coord::new((-1, 0)), Coord::new((0, 0))),
-            Self::ToUp => Rectangle::new(Coord::new((0, -1)), Coord::new((0, 0))),
-            Self::ToRight => Rectangle::new(Coord::new((0, 0)), Coord::new((1, 0))),
-            Self::ToDown => Rectangle::new(Coord::new((0, 0)), Coord::new((0, 1))),
+            Self::Left => Rectangle::new(Coord::new((-1, 0)), Coord::new((0, 0))),
+            Self::Up => Rectangle::new(Coord::new((0, -1)), Coord::new((0, 0))),
+            Self::Right => Rectangle::new(Coord::new((0, 0)), Coord::new((1, 0))),
+            Self::Down => Rectangle::new(Coord::new((0, 0)), Coord::new((0, 1))),
         }
     }
 
     fn make_prob_overwrapped<'a>(&self, output: &'a Output, rect: &Rectangle) -> &'a Vec<usize> {
         match self {
-            Expander::ToLeft => &output.rightside_hash[rect.leftup.x as usize],
-            Expander::ToUp => &output.bottomside_hash[rect.leftup.y as usize],
-            Expander::ToRight => &output.leftside_hash[rect.rightdown.x as usize],
-            Expander::ToDown => &output.topside_hash[rect.rightdown.y as usize],
+            Expander::Left => &output.rightside_hash[rect.leftup.x as usize],
+            Expander::Up => &output.bottomside_hash[rect.leftup.y as usize],
+            Expander::Right => &output.leftside_hash[rect.rightdown.x as usize],
+            Expander::Down => &output.topside_hash[rect.rightdown.y as usize],
         }
     }
 
     fn elems() -> [Self; 4] {
         [
-            Expander::ToLeft,
-            Expander::ToUp,
-            Expander::ToRight,
-            Expander::ToDown,
+            Expander::Left,
+            Expander::Up,
+            Expander::Right,
+            Expander::Down,
         ]
     }
 }
@@ -247,7 +201,7 @@ impl Output {
         // 希望地点に面積1で置く
         for (id, req) in input.requests.iter().enumerate() {
             let Request { spot: pos, area: _ } = req;
-            let area1_rect = Rectangle::new(pos.clone(), pos.clone().plus(&Coord::new((1, 1))));
+            let area1_rect = Rectangle::new(*pos, pos.plus(&Coord::new((1, 1))));
             results.push(area1_rect);
 
             leftside_hash[area1_rect.leftup.x as usize].push(id);
@@ -334,10 +288,6 @@ impl Output {
         self.bottomside_hash[next_rect.rightdown.y as usize].push(id);
     }
 
-    fn calc_game_score(&self) -> usize {
-        (self.score / self.input.n as f64 * 1e9).round() as usize
-    }
-
     fn get_current_score(&self, id: usize) -> f64 {
         self.results[id].calc_score(id, &self.input)
     }
@@ -349,14 +299,14 @@ impl Output {
         next_rect: &Rectangle,
         expander: Expander,
     ) -> Option<(Vec<(usize, Rectangle)>, f64)> {
-        let prob_overwrapped = expander.make_prob_overwrapped(&self, &cur_rect);
+        let prob_overwrapped = expander.make_prob_overwrapped(self, cur_rect);
 
         let mut other_changes = Vec::<(usize, Rectangle)>::new(); // (id, next_rect)
         let mut score_diff = 0.0;
 
         for &i in prob_overwrapped {
             let other = self.results[i];
-            if other.does_include_rect(&next_rect) {
+            if other.does_include_rect(next_rect) {
                 let next_other = other.plus(&expander.pushed_delta());
                 // 押し広げた結果、不正な長方形を生まないか？
                 if !next_other.is_valid() {
@@ -378,184 +328,224 @@ fn format_result_row(rect: &Rectangle) -> String {
 }
 
 fn print_result_row(rect: &Rectangle) {
-    println!("{}", format_result_row(&rect));
+    println!("{}", format_result_row(rect));
 }
 
-fn annealing(output: &mut Output, system_time: &SystemTime) {
-    // 開始温度(スコア差の最大値にすると良さそう。開始直後に35%くらいの確率でこの差量を受け入れる)
-    let start_temp: f64 = 1e-3;
-    // 終了温度(終盤に悪化遷移を35%程度許容できる値にすると良さそう)
-    let end_temp: f64 = 5e-6;
-
-    let strat_time = system_time.elapsed().unwrap().as_millis();
-    let tl: f64 = ((TIMEOUT_MS - strat_time) - 4100) as f64; // 焼きなまし時間(ミリ秒)
+// 焼きなましの1手: 長方形をまとめて置き換える (押し出される長方形も含む)
+struct RectMove {
+    changes: Vec<(usize, Rectangle)>, // (id, next_rect)
+    prev: Vec<(usize, Rectangle)>,    // 取り消し用
+    score_diff: f64,
+}
 
-    let mut rng = thread_rng();
+impl RectMove {
+    fn new(
+        output: &Output,
+        id: usize,
+        next_rect: Rectangle,
+        other_changes: Vec<(usize, Rectangle)>,
+        score_diff: f64,
+    ) -> Self {
+        let mut changes = vec![(id, next_rect)];
+        changes.extend(other_changes);
+        let prev = changes
+            .iter()
+            .map(|&(i, _)| (i, output.results[i]))
+            .collect();
 
-    let mut temp;
-    // 初期値をセット
-    let mut best_score = output.score;
-    let mut best_out = output.results.clone();
+        RectMove {
+            changes,
+            prev,
+            score_diff,
+        }
+    }
+}
 
-    const LOOP_NUM: usize = 1000;
-    let mut loop_cnt = 0;
+impl Annealing for Output {
+    type Move = RectMove;
+    type Snapshot = (Vec<Rectangle>, f64);
 
-    loop {
-        let spent_time_rate = (system_time.elapsed().unwrap().as_millis() - strat_time) as f64 / tl; // (0.0, 1.0)
-        if spent_time_rate >= 1.0 {
-            break;
-        }
-        // 温度。段々下がっていく。
-        temp = start_temp + (end_temp - start_temp) * spent_time_rate;
-
-        for _ in 0..LOOP_NUM {
-            let id = rng.gen_range(0, output.input.n);
-            let rect = &output.results[id];
-            let cur_score = rect.calc_score(id, &output.input);
-
-            /* 変更処理の実行 */
-            if rng.gen_bool(1.0) {
-                /* 一辺を動かす */
-                let direction = rng.gen_range(0, 2); // leftup, rightdown
-
-                let next_rect = if direction == 0 {
-                    let next_reprs = rect.leftup.mk_4dir();
-                    let i = rng.gen_range(0, next_reprs.len());
-                    Rectangle::new(next_reprs[i], rect.rightdown)
-                } else {
-                    let next_reprs = rect.rightdown.mk_4dir();
-                    let i = rng.gen_range(0, next_reprs.len());
-                    Rectangle::new(rect.leftup, next_reprs[i])
-                };
+    fn score(&self) -> f64 {
+        self.score
+    }
 
-                if !next_rect.is_valid() {
-                    continue;
-                }
+    fn propose(&self, rng: &mut Rng) -> Option<RectMove> {
+        let id = rng.gen_range(0, self.input.n);
+        let rect = &self.results[id];
+        let cur_score = rect.calc_score(id, &self.input);
 
-                let expander = {
-                    if next_rect.leftup.x == rect.leftup.x - 1 {
-                        Some(Expander::ToLeft)
-                    } else if next_rect.leftup.y == rect.leftup.y - 1 {
-                        Some(Expander::ToUp)
-                    } else if next_rect.rightdown.x == rect.rightdown.x + 1 {
-                        Some(Expander::ToRight)
-                    } else if next_rect.rightdown.y == rect.rightdown.y + 1 {
-                        Some(Expander::ToDown)
-                    } else {
-                        None
-                    }
-                };
+        /* 変更処理の実行 */
+        if rng.gen_bool(1.0) {
+            /* 一辺を動かす */
+            let direction = rng.gen_range(0, 2); // leftup, rightdown
 
-                let next_score = next_rect.calc_score(id, &output.input);
-                let mut score_diff = next_score - cur_score;
+            let next_rect = if direction == 0 {
+                let next_reprs = rect.leftup.mk_4dir(SIDE + 1, SIDE + 1);
+                let i = rng.gen_range(0, next_reprs.len());
+                Rectangle::new(next_reprs[i], rect.rightdown)
+            } else {
+                let next_reprs = rect.rightdown.mk_4dir(SIDE + 1, SIDE + 1);
+                let i = rng.gen_range(0, next_reprs.len());
+                Rectangle::new(rect.leftup, next_reprs[i])
+            };
 
-                let mut other_changes = Vec::new(); // (id, next_rect)
+            if !next_rect.is_valid() {
+                return None;
+            }
 
-                if let Some(expander) = expander {
-                    match output.expand(rect, &next_rect, expander) {
-                        None => continue,
-                        Some((o_changes, diff)) => {
-                            other_changes = o_changes;
-                            score_diff += diff;
-                        }
-                    };
+            let expander = {
+                if next_rect.leftup.x == rect.leftup.x - 1 {
+                    Some(Expander::Left)
+                } else if next_rect.leftup.y == rect.leftup.y - 1 {
+                    Some(Expander::Up)
+                } else if next_rect.rightdown.x == rect.rightdown.x + 1 {
+                    Some(Expander::Right)
+                } else if next_rect.rightdown.y == rect.rightdown.y + 1 {
+                    Some(Expander::Down)
+                } else {
+                    None
                 }
+            };
 
-                // スコアが増すか、`e^(score差 / T)` の確率にヒットしたら
-                // `score差` が負の数なのが肝
-                if score_diff > 0.0 || rng.gen_bool(f64::exp(score_diff / temp)) {
-                    output.update_results(id, next_rect);
-                    for (i, other) in other_changes {
-                        output.update_results(i, other);
-                    }
-                }
-            } else {
-                /* 短い辺を縮めて、長い辺を伸ばす */
-                let shrink_dir_b = rng.gen_range(0, 2); // 0: left or up, 1: right or down
-                let expand_dir_b = rng.gen_range(0, 2); // 0: left or up, 1: right or down
-
-                let (shrink_dir, expand_dir) = match rect.long_side() {
-                    Some(RectSide::Width) | None => (
-                        // shrink
-                        if shrink_dir_b == 0 {
-                            Expander::ToUp
-                        } else {
-                            Expander::ToDown
-                        },
-                        // expand
-                        if expand_dir_b == 0 {
-                            Expander::ToLeft
-                        } else {
-                            Expander::ToRight
-                        },
-                    ),
-                    Some(RectSide::Height) => (
-                        if shrink_dir_b == 0 {
-                            Expander::ToLeft
-                        } else {
-                            Expander::ToRight
-                        },
-                        if expand_dir_b == 0 {
-                            Expander::ToUp
-                        } else {
-                            Expander::ToDown
-                        },
-                    ),
-                };
-
-                let pre_next_rect = rect.minus(&shrink_dir.push_delta());
-                let next_rect = pre_next_rect.plus(&expand_dir.push_delta());
-                if !next_rect.is_valid() {
-                    continue;
-                }
+            let next_score = next_rect.calc_score(id, &self.input);
+            let mut score_diff = next_score - cur_score;
 
-                let mut score_diff = next_rect.calc_score(id, &output.input) - cur_score;
-                let other_changes: Vec<(usize, Rectangle)> =
-                    match output.expand(&pre_next_rect, &next_rect, expand_dir) {
-                        None => {
-                            continue;
-                        }
-                        Some((other_changes, sc_diff)) => {
-                            score_diff += sc_diff;
-                            other_changes
-                        }
-                    };
-
-                // todo: DRYにする
-                // スコアが増すか、`e^(score差 / T)` の確率にヒットしたら
-                // `score差` が負の数なのが肝
-                if score_diff > 0.0 || rng.gen_bool(f64::exp(score_diff / temp)) {
-                    output.update_results(id, next_rect);
-                    for (i, other) in other_changes {
-                        output.update_results(i, other);
-                    }
-                }
+            let mut other_changes = Vec::new(); // (id, next_rect)
+
+            if let Some(expander) = expander {
+                let (o_changes, diff) = self.expand(rect, &next_rect, expander)?;
+                other_changes = o_changes;
+                score_diff += diff;
             }
 
-            if output.score > best_score {
-                // ベストスコアの更新
-                best_score = output.score;
-                best_out = output.results.clone();
+            Some(RectMove::new(
+                self,
+                id,
+                next_rect,
+                other_changes,
+                score_diff,
+            ))
+        } else {
+            /* 短い辺を縮めて、長い辺を伸ばす */
+            let shrink_dir_b = rng.gen_range(0, 2); // 0: left or up, 1: right or down
+            let expand_dir_b = rng.gen_range(0, 2); // 0: left or up, 1: right or down
+
+            let (shrink_dir, expand_dir) = match rect.long_side() {
+                Some(RectSide::Width) | None => (
+                    // shrink
+                    if shrink_dir_b == 0 {
+                        Expander::Up
+                    } else {
+                        Expander::Down
+                    },
+                    // expand
+                    if expand_dir_b == 0 {
+                        Expander::Left
+                    } else {
+                        Expander::Right
+                    },
+                ),
+                Some(RectSide::Height) => (
+                    if shrink_dir_b == 0 {
+                        Expander::Left
+                    } else {
+                        Expander::Right
+                    },
+                    if expand_dir_b == 0 {
+                        Expander::Up
+                    } else {
+                        Expander::Down
+                    },
+                ),
+            };
+
+            let pre_next_rect = rect.minus(&shrink_dir.push_delta());
+            let next_rect = pre_next_rect.plus(&expand_dir.push_delta());
+            if !next_rect.is_valid() {
+                return None;
             }
+
+            let (other_changes, sc_diff) = self.expand(&pre_next_rect, &next_rect, expand_dir)?;
+            let score_diff = next_rect.calc_score(id, &self.input) - cur_score + sc_diff;
+
+            Some(RectMove::new(
+                self,
+                id,
+                next_rect,
+                other_changes,
+                score_diff,
+            ))
         }
+    }
 
-        loop_cnt += 1;
+    fn delta(&mut self, mv: &RectMove) -> f64 {
+        mv.score_diff
+    }
 
-        // 出力
-        for res in &output.results {
-            print_result_row(&res);
+    fn apply(&mut self, mv: &RectMove) {
+        for &(i, rect) in &mv.changes {
+            self.update_results(i, rect);
         }
     }
 
-    eprintln!("{} * {}回ループ", loop_cnt, LOOP_NUM);
+    fn revert(&mut self, mv: &RectMove) {
+        for &(i, rect) in mv.prev.iter().rev() {
+            self.update_results(i, rect);
+        }
+    }
 
-    output.results = best_out;
-    output.score = best_score;
+    fn snapshot(&self) -> Self::Snapshot {
+        (self.results.clone(), self.score)
+    }
+
+    fn restore(&mut self, (results, score): Self::Snapshot) {
+        for (id, rect) in results.into_iter().enumerate() {
+            self.update_results(id, rect);
+        }
+        self.score = score;
+    }
+}
+
+// PARAMS / PARAMS_FILE で変えられるもの
+const PARAM_NAMES: [&str; 3] = ["start_temp", "end_temp", "loop_num"];
+
+fn annealing(output: &mut Output, timer: &Timer, params: &Params) {
+    // 開始温度(スコア差の最大値にすると良さそう。開始直後に35%くらいの確率でこの差量を受け入れる)
+    let start_temp: f64 = params.get("start_temp", 1e-3);
+    // 終了温度(終盤に悪化遷移を35%程度許容できる値にすると良さそう)
+    let end_temp: f64 = params.get("end_temp", 5e-6);
+
+    let tl = timer.remaining(); // 焼きなまし時間(ミリ秒)
+
+    let loop_num: usize = params.get("loop_num", 1000);
+
+    let annealer = Annealer::new(
+        Linear {
+            start: start_temp,
+            end: end_temp,
+        },
+        Budget::Time(tl),
+    )
+    .interval(loop_num);
+    let mut rng = Rng::from_time();
+
+    let stats = annealer.run(output, &mut rng, |output, _| {
+        // 出力
+        for res in &output.results {
+            print_result_row(res);
+        }
+    });
+
+    eprintln!("{} * {}回ループ", stats.rounds, loop_num);
 }
 
 #[fastout]
 fn main() {
-    let system_time = SystemTime::now();
+    let timer = Timer::new(TIMEOUT_MS).margin(TIMEOUT_MARGIN_MS);
+    let params = Params::from_env();
+    for name in params.unknown(&PARAM_NAMES) {
+        eprintln!("unknown param: {}", name);
+    }
 
     input! {
         n: usize,
@@ -565,18 +555,19 @@ fn main() {
     let input = Input::new(n, xyr);
     let mut output = Output::new(input);
 
+    eprintln!("start annealing at {}ms.", timer.elapsed());
+    annealing(&mut output, &timer, &params);
+
+    // 公式と同じ尺度 (満足度の平均 * 10^9)
     eprintln!(
-        "start annealing at {}ms.",
-        system_time.elapsed().unwrap().as_millis()
+        "score: {}",
+        (1e9 * output.score / output.input.n as f64).round()
     );
-    annealing(&mut output, &system_time);
-
-    eprintln!("{}", output.score / output.input.n as f64);
 
     // 出力
     for res in output.results {
         print_result_row(&res);
     }
 
-    eprintln!("{}ms", system_time.elapsed().unwrap().as_millis());
+    eprintln!("{}ms", timer.elapsed());
 }
@@ -201,6 +201,6 @@ fn main() {
 	}
 	let input = read_input(&std::env::args().nth(1).unwrap());
 	let output = read_output(&input, &std::env::args().nth(2).unwrap());
-	println!("{}", score(&input, &output));
+	println!("Score = {}", score(&input, &output));
 	vis(&input, &output, true);
 }
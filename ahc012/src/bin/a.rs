@@ -9,134 +9,335 @@ use std::collections::*;
 #[allow(unused_imports)]
 use std::io::Write;
 
-#[allow(unused_imports)]
-use rand::rngs::ThreadRng;
-#[allow(unused_imports)]
-use rand::seq::SliceRandom;
-#[allow(unused_imports)]
-use rand::{thread_rng, Rng};
-use std::time::SystemTime;
-
-#[allow(dead_code)]
-const LIMIT_TIME: usize = 3_000; // ms
+use heuristic_core::geom::Coord;
+use heuristic_core::params::Params;
+use heuristic_core::rng::Rng;
+use heuristic_core::timer::Timer;
+
+// 以下の4つは PARAMS / PARAMS_FILE で変えられる (limit_time, limit_margin, k, evaluator)
+const LIMIT_TIME: u128 = 3_000; // ms
+const LIMIT_MARGIN: u128 = 100; // ms
 const K: usize = 100; // カット数
+const EVALUATOR: Evaluator = Evaluator::Weighted; // score | shortage | weighted | empty_area
+const PARAM_NAMES: [&str; 4] = ["limit_time", "limit_margin", "k", "evaluator"];
 const RADIUS: isize = 10_000;
+const COORD_LIMIT: isize = 1_000_000_000; // 出力座標の絶対値の上限
+const NUDGE_MAX_STEP: isize = 10; // 苺を避けるためにずらす最大量
+const NUDGE_DELTAS: [(isize, isize); 8] = [
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+// ビームサーチ
+const BEAM_INIT_WIDTH: usize = 10;
+const BEAM_MIN_WIDTH: usize = 2;
+const BEAM_MAX_WIDTH: usize = 300;
+const RANDOM_CANDIDATE_NUM: usize = 3; // 大きいピースを通るランダムな直線
+const BALANCED_CANDIDATE_NUM: usize = 4; // 目標サイズで切り分ける直線
+const SPLIT_DIR_NUM: usize = 4; // find_split_line で試す向きの数
+const SPLIT_DIR_SCALE: f64 = 1000.0; // 向きベクトルの大きさ
+const EMPTY_AREA_WEIGHT: f64 = 10.0; // Evaluator::EmptyArea で空のピースの面積割合に掛ける重み
+
+const DISK_VERTEX_NUM: usize = 64; // ケーキを外接正多角形で近似する頂点数
+
+// 出力として許される座標か
+fn in_bounds(p: &Coord) -> bool {
+    p.x.abs() <= COORD_LIMIT && p.y.abs() <= COORD_LIMIT
+}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-struct Coord {
-    x: isize,
-    y: isize,
+// 直線 pq に対する点の位置 (正: 左側, 負: 右側, 0: 線上)
+fn side_of(p: &Coord, q: &Coord, (x, y): (isize, isize)) -> i64 {
+    (q.x - p.x) as i64 * (y - p.y) as i64 - (q.y - p.y) as i64 * (x - p.x) as i64
 }
-#[allow(dead_code)]
-impl Coord {
-    fn new(p: (isize, isize)) -> Self {
-        Coord { x: p.0, y: p.1 }
+
+// 提出可能なカットか (p != q, 座標が範囲内, どの苺も通らない)
+fn is_valid_cut(input: &Input, (p, q): &(Coord, Coord)) -> bool {
+    p != q && in_bounds(p) && in_bounds(q) && input.xy.iter().all(|&s| side_of(p, q, s) != 0)
+}
+
+// カット候補の検査と修復。
+// p == q なら q をずらして直線にし、苺を通るなら整数座標のまま平行移動して避ける。
+// 修復できなければ None
+fn validate_cut(input: &Input, p: Coord, q: Coord) -> Option<(Coord, Coord)> {
+    if !in_bounds(&p) || !in_bounds(&q) {
+        return None;
+    }
+    let q = if p == q {
+        let dx = if p.x < COORD_LIMIT { 1 } else { -1 };
+        p.plus(&Coord::new((dx, 0)))
+    } else {
+        q
+    };
+
+    if is_valid_cut(input, &(p, q)) {
+        return Some((p, q));
     }
-    fn from_usize_pair(p: (usize, usize)) -> Self {
-        Coord {
-            x: p.0 as isize,
-            y: p.1 as isize,
+    for step in 1..=NUDGE_MAX_STEP {
+        for &(dx, dy) in &NUDGE_DELTAS {
+            let d = Coord::new((dx * step, dy * step));
+            let cut = (p.plus(&d), q.plus(&d));
+            if is_valid_cut(input, &cut) {
+                return Some(cut);
+            }
         }
     }
 
-    // fn _in_field(&self) -> bool {
-    //     (0 <= self.x && self.x < SIDE as isize) && (0 <= self.y && self.y < H as isize)
-    // }
+    None
+}
 
-    // ペアへの変換
-    fn to_pair(&self) -> (isize, isize) {
-        (self.x, self.y)
+// ピースを切断面で (左側, 右側) に二分割する
+// 線上の苺はどちらのピースにも入らないので、cut は validate_cut 済みであること
+fn split_piece(
+    input: &Input,
+    piece: &Vec<usize>,
+    (p, q): &(Coord, Coord),
+) -> (Vec<usize>, Vec<usize>) {
+    let mut left = vec![];
+    let mut right = vec![];
+    for &j in piece {
+        let side = side_of(p, q, input.xy[j]);
+        if side > 0 {
+            left.push(j);
+        } else if side < 0 {
+            right.push(j);
+        }
     }
 
-    // マンハッタン距離
-    fn distance(&self, that: &Self) -> isize {
-        (self.x - that.x).abs() + (self.y - that.y).abs()
+    (left, right)
+}
+
+// 各ピースを切断面で二分割する
+fn split_pieces(input: &Input, pieces: &Vec<Vec<usize>>, cut: &(Coord, Coord)) -> Vec<Vec<usize>> {
+    let mut new_pieces = Vec::with_capacity(pieces.len() * 2);
+    for piece in pieces {
+        let (left, right) = split_piece(input, piece, cut);
+        if !left.is_empty() {
+            new_pieces.push(left);
+        }
+        if !right.is_empty() {
+            new_pieces.push(right);
+        }
     }
 
-    // 四則演算
-    fn plus(&self, that: &Self) -> Self {
-        Coord::new((self.x + that.x, self.y + that.y))
+    new_pieces
+}
+
+// 苺の個数毎にピースをカウント
+fn count_b(pieces: &Vec<Vec<usize>>) -> Vec<usize> {
+    let mut b = vec![0; 10];
+    for piece in pieces {
+        if piece.len() <= 10 {
+            b[piece.len() - 1] += 1;
+        }
     }
-    fn minus(&self, that: &Self) -> Self {
-        Coord::new((self.x - that.x, self.y - that.y))
+
+    b
+}
+
+// => (b, サイズ降順のピース一覧)
+fn make_b(input: &Input, out: &Vec<(Coord, Coord)>) -> (Vec<usize>, Vec<Vec<usize>>) {
+    // ケーキのビースに乗ってる苺の番号一覧の一覧
+    let mut pieces = vec![(0..input.n).collect::<Vec<_>>()];
+    for cut in out {
+        pieces = split_pieces(input, &pieces, cut);
     }
 
-    fn access_matrix<'a, T>(&'a self, mat: &'a Vec<Vec<T>>) -> &'a T {
-        &mat[self.y as usize][self.x as usize]
+    let b = count_b(&pieces);
+
+    pieces.sort_by_key(|e| -(e.len() as isize));
+    (b, pieces)
+}
+
+// ピースの形 (凸多角形, 頂点は反時計回り)
+#[derive(Debug, Clone)]
+struct Polygon {
+    vertices: Vec<(f64, f64)>,
+}
+impl Polygon {
+    // ケーキを内包する外接正多角形
+    fn disk() -> Self {
+        let r = RADIUS as f64 / (std::f64::consts::PI / DISK_VERTEX_NUM as f64).cos();
+        let vertices = (0..DISK_VERTEX_NUM)
+            .map(|k| {
+                let theta = 2.0 * std::f64::consts::PI * k as f64 / DISK_VERTEX_NUM as f64;
+                (r * theta.cos(), r * theta.sin())
+            })
+            .collect();
+
+        Self { vertices }
     }
 
-    fn set_matrix<T>(&self, mat: &mut Vec<Vec<T>>, e: T) {
-        mat[self.y as usize][self.x as usize] = e;
+    // 面積 (靴紐公式)
+    fn area(&self) -> f64 {
+        let n = self.vertices.len();
+        let mut acc = 0.0;
+        for i in 0..n {
+            let (x1, y1) = self.vertices[i];
+            let (x2, y2) = self.vertices[(i + 1) % n];
+            acc += x1 * y2 - x2 * y1;
+        }
+
+        acc / 2.0
     }
 
-    // user define
-    fn to_str(&self) -> String {
-        format!("{} {}", self.x, self.y)
+    // 重心。面積0なら None
+    fn centroid(&self) -> Option<(f64, f64)> {
+        let area = self.area();
+        if area <= 0.0 {
+            return None;
+        }
+
+        let n = self.vertices.len();
+        let mut cx = 0.0;
+        let mut cy = 0.0;
+        for i in 0..n {
+            let (x1, y1) = self.vertices[i];
+            let (x2, y2) = self.vertices[(i + 1) % n];
+            let cross = x1 * y2 - x2 * y1;
+            cx += (x1 + x2) * cross;
+            cy += (y1 + y2) * cross;
+        }
+
+        Some((cx / (6.0 * area), cy / (6.0 * area)))
     }
-}
 
-// => (b, max_piece)
-fn make_b(input: &Input, out: &Vec<(Coord, Coord)>) -> (Vec<usize>, Vec<usize>, Vec<usize>) {
-    // ケーキのビースに乗ってる苺の番号一覧の一覧
-    let mut pieces = vec![(0..input.n).collect::<Vec<_>>()];
-    for (p, q) in out {
-        let Coord { x: px, y: py } = p;
-        let Coord { x: qx, y: qy } = q;
-
-        let mut new_pieces = vec![];
-        // 各ピースを切断面で二分割
-        for piece in pieces {
-            let mut left = vec![];
-            let mut right = vec![];
-            for j in piece {
-                let (x, y) = input.xy[j];
-                let side = (qx - px) as i64 * (y - py) as i64 - (qy - py) as i64 * (x - px) as i64;
-                if side > 0 {
-                    left.push(j);
-                } else if side < 0 {
-                    right.push(j);
-                }
+    fn vertices(&self) -> &[(f64, f64)] {
+        &self.vertices
+    }
+
+    // 切断面で (左側, 右側) に分ける。片側が空なら頂点無しの多角形になる
+    fn split(&self, (p, q): &(Coord, Coord)) -> (Self, Self) {
+        let side = |(x, y): (f64, f64)| {
+            (q.x - p.x) as f64 * (y - p.y as f64) - (q.y - p.y) as f64 * (x - p.x as f64)
+        };
+
+        let n = self.vertices.len();
+        let mut left = vec![];
+        let mut right = vec![];
+        for i in 0..n {
+            let v1 = self.vertices[i];
+            let v2 = self.vertices[(i + 1) % n];
+            let s1 = side(v1);
+            let s2 = side(v2);
+            if s1 >= 0.0 {
+                left.push(v1);
             }
-            if left.len() > 0 {
-                new_pieces.push(left);
+            if s1 <= 0.0 {
+                right.push(v1);
             }
-            if right.len() > 0 {
-                new_pieces.push(right);
+            // 辺が切断面を跨ぐなら交点を両側に追加
+            if (s1 > 0.0 && s2 < 0.0) || (s1 < 0.0 && s2 > 0.0) {
+                let t = s1 / (s1 - s2);
+                let cross = (v1.0 + (v2.0 - v1.0) * t, v1.1 + (v2.1 - v1.1) * t);
+                left.push(cross);
+                right.push(cross);
             }
         }
-        pieces = new_pieces;
+        if left.len() < 3 {
+            left.clear();
+        }
+        if right.len() < 3 {
+            right.clear();
+        }
+
+        (Self { vertices: left }, Self { vertices: right })
     }
+}
 
-    // 年数毎にカウント
-    let mut b = vec![0; 10];
-    for piece in &pieces {
-        if piece.len() <= 10 {
-            b[piece.len() - 1] += 1;
+// カット列から (苺の番号一覧, 形) の一覧を作る (苺の無いピースも含む)
+fn make_pieces(input: &Input, out: &[(Coord, Coord)]) -> Vec<(Vec<usize>, Polygon)> {
+    let mut pieces = vec![((0..input.n).collect::<Vec<_>>(), Polygon::disk())];
+    for cut in out {
+        let mut next = Vec::with_capacity(pieces.len() * 2);
+        for (piece, polygon) in &pieces {
+            let (left, right) = split_piece(input, piece, cut);
+            let (left_polygon, right_polygon) = polygon.split(cut);
+            if !left_polygon.vertices().is_empty() {
+                next.push((left, left_polygon));
+            }
+            if !right_polygon.vertices().is_empty() {
+                next.push((right, right_polygon));
+            }
         }
+        pieces = next;
     }
 
-    pieces.sort_by_key(|e| -(e.len() as isize));
-    (b, pieces[0].clone(), pieces[1].clone())
+    pieces
 }
 
-fn compute_score(input: &Input, b: &Vec<usize>) -> isize {
+// ピースの可視化用 (bin/pieces.rs が読む)
+// 1行1ピースで "苺の数 重心x 重心y 頂点x 頂点y ..."
+fn write_pieces(path: &str, pieces: &[(Vec<usize>, Polygon)]) -> std::io::Result<()> {
+    let mut f = std::io::BufWriter::new(std::fs::File::create(path)?);
+    for (piece, polygon) in pieces {
+        let (cx, cy) = match polygon.centroid() {
+            Some(c) => c,
+            None => continue,
+        };
+        write!(f, "{} {:.1} {:.1}", piece.len(), cx, cy)?;
+        for (x, y) in polygon.vertices() {
+            write!(f, " {:.1} {:.1}", x, y)?;
+        }
+        writeln!(f)?;
+    }
+
+    f.flush()
+}
+
+// 取り除いてもピースの分け方が変わらないカットを取り除く。
+// 苺ごとに各カットのどちら側にいるかをビットで持ち、カットを外しても種類数が減らなければ冗長
+fn remove_redundant_cuts(input: &Input, out: &[(Coord, Coord)]) -> Vec<(Coord, Coord)> {
+    let sides = input
+        .xy
+        .iter()
+        .map(|&s| {
+            out.iter()
+                .enumerate()
+                .filter(|(_, (p, q))| side_of(p, q, s) > 0)
+                .fold(0u128, |acc, (i, _)| acc | 1 << i)
+        })
+        .collect::<Vec<_>>();
+    let count = |mask: u128| sides.iter().map(|s| s & mask).collect::<HashSet<_>>().len();
+
+    let mut mask = !0u128;
+    let piece_num = count(mask);
+    for i in (0..out.len()).rev() {
+        let next_mask = mask & !(1 << i);
+        if count(next_mask) == piece_num {
+            mask = next_mask;
+        }
+    }
+
+    out.iter()
+        .enumerate()
+        .filter(|(i, _)| mask >> i & 1 == 1)
+        .map(|(_, &cut)| cut)
+        .collect()
+}
+
+fn compute_score(input: &Input, b: &[usize]) -> isize {
     // スコア計算
     let mut num = 0;
     let mut den = 0;
-    for d in 0..10 {
-        num += input.a[d].min(b[d]);
-        den += input.a[d];
+    for (&a, &b) in input.a.iter().zip(b) {
+        num += a.min(b);
+        den += a;
     }
-    let score = (1e6 * num as f64 / den as f64).round() as isize;
 
-    score
+    (1e6 * num as f64 / den as f64).round() as isize
 }
 
-fn compute_shortage(input: &Input, b: &Vec<usize>) -> isize {
+fn compute_shortage(input: &Input, b: &[usize]) -> isize {
     let mut shortage = 0;
-    for d in 0..10 {
-        if b[d] < input.a[d] {
-            shortage += input.a[d] - b[d];
+    for (&a, &b) in input.a.iter().zip(b) {
+        if b < a {
+            shortage += a - b;
         }
     }
 
@@ -147,12 +348,275 @@ struct Input {
     n: usize,
     a: Vec<usize>,
     xy: Vec<(isize, isize)>,
+    evaluator: Evaluator,
+}
+
+// ビームの順位付けに使う評価 (小さいほど良い)
+#[derive(Clone, Copy)]
+enum Evaluator {
+    Score,     // compute_score
+    Shortage,  // compute_shortage
+    Weighted,  // 不足数 + 大きいピースの不足に重み (eval)
+    EmptyArea, // Weighted から、苺の無いピースの面積割合の分を引く
+}
+impl Evaluator {
+    // ピースの形 (面積) を使うか。使わないならビームで多角形を切らない
+    fn needs_area(&self) -> bool {
+        matches!(self, Evaluator::EmptyArea)
+    }
+
+    fn eval(&self, input: &Input, b: &[usize], empty_area: f64) -> f64 {
+        match self {
+            Evaluator::Score => -compute_score(input, b) as f64,
+            Evaluator::Shortage => compute_shortage(input, b) as f64,
+            Evaluator::Weighted => compute_shortage(input, b) as f64 + eval(input, b),
+            Evaluator::EmptyArea => {
+                let disk_area = std::f64::consts::PI * (RADIUS * RADIUS) as f64;
+                compute_shortage(input, b) as f64 + eval(input, b)
+                    - EMPTY_AREA_WEIGHT * empty_area / disk_area
+            }
+        }
+    }
+}
+impl std::str::FromStr for Evaluator {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "score" => Ok(Evaluator::Score),
+            "shortage" => Ok(Evaluator::Shortage),
+            "weighted" => Ok(Evaluator::Weighted),
+            "empty_area" => Ok(Evaluator::EmptyArea),
+            _ => Err(format!("unknown evaluator: {}", s)),
+        }
+    }
+}
+
+// カット途中の状態
+#[derive(Clone)]
+struct BeamState {
+    out: Vec<(Coord, Coord)>,
+    pieces: Vec<Vec<usize>>, // サイズ降順
+    polygons: Vec<Polygon>,  // pieces[i] の形 (evaluator が面積を使うときだけ持つ。それ以外は空)
+    empty_area: f64,         // 苺の無いピースの面積の合計
+    b: Vec<usize>,
+    eval: f64,
+}
+impl BeamState {
+    fn new(input: &Input) -> Self {
+        let (b, pieces) = make_b(input, &vec![]);
+        let eval = input.evaluator.eval(input, &b, 0.0);
+        Self {
+            out: vec![],
+            pieces,
+            polygons: if input.evaluator.needs_area() {
+                vec![Polygon::disk()]
+            } else {
+                vec![]
+            },
+            empty_area: 0.0,
+            b,
+            eval,
+        }
+    }
+
+    // ピースが増えない無駄なカットなら None
+    fn apply(&self, input: &Input, cut: (Coord, Coord)) -> Option<Self> {
+        let mut cells = Vec::with_capacity(self.pieces.len() * 2);
+        let mut empty_area = self.empty_area;
+        for (i, piece) in self.pieces.iter().enumerate() {
+            let (left, right) = split_piece(input, piece, &cut);
+            let (left_polygon, right_polygon) = match self.polygons.get(i) {
+                Some(polygon) => {
+                    let (l, r) = polygon.split(&cut);
+                    (Some(l), Some(r))
+                }
+                None => (None, None),
+            };
+            let halves = std::iter::once((left, left_polygon))
+                .chain(std::iter::once((right, right_polygon)));
+            for (half, polygon) in halves {
+                if !half.is_empty() {
+                    cells.push((half, polygon));
+                } else if let Some(polygon) = polygon {
+                    empty_area += polygon.area();
+                }
+            }
+        }
+        if cells.len() == self.pieces.len() {
+            return None;
+        }
+        cells.sort_by_key(|(e, _)| -(e.len() as isize));
+        let (pieces, polygons): (Vec<_>, Vec<_>) = cells.into_iter().unzip();
+        let polygons = polygons.into_iter().flatten().collect();
+
+        let b = count_b(&pieces);
+        let eval = input.evaluator.eval(input, &b, empty_area);
+        let mut out = self.out.clone();
+        out.push(cut);
+
+        Some(Self {
+            out,
+            pieces,
+            polygons,
+            empty_area,
+            b,
+            eval,
+        })
+    }
+
+    // 次のカット候補 (validate_cut 済み)
+    fn candidates(&self, input: &Input, rng: &mut Rng) -> Vec<(Coord, Coord)> {
+        let mut cands = vec![];
+
+        // 大きい2ピースの重心を結ぶ直線
+        if self.pieces.len() >= 2 {
+            let p = central_point(input, &self.pieces[0]);
+            let q = central_point(input, &self.pieces[1]);
+            cands.push((p, q));
+        }
+
+        // 大きいピースの重心を通るランダムな向きの直線
+        for _ in 0..RANDOM_CANDIDATE_NUM {
+            let i = rng.gen_range(0, self.pieces.len().min(3));
+            let p = central_point(input, &self.pieces[i]);
+            let d = Coord::new((
+                rng.gen_range_i64(-RADIUS as i64, RADIUS as i64) as isize,
+                rng.gen_range_i64(-RADIUS as i64, RADIUS as i64) as isize,
+            ));
+            cands.push((p, p.plus(&d)));
+        }
+
+        // 足りないサイズ d のピースを切り出す直線
+        let targets = (1..=10)
+            .filter(|&d| self.b[d - 1] < input.a[d - 1])
+            .collect::<Vec<_>>();
+        if !targets.is_empty() {
+            for _ in 0..BALANCED_CANDIDATE_NUM {
+                let d = targets[rng.gen_range(0, targets.len())];
+                let large = self.pieces.iter().filter(|e| e.len() > d).count();
+                if large == 0 {
+                    continue;
+                }
+                let piece = &self.pieces[rng.gen_range(0, large)];
+                let dirs = sweep_dirs(SPLIT_DIR_NUM, rng.gen_f64() * std::f64::consts::PI);
+                if let Some(cut) = find_split_line(input, piece, d, &dirs) {
+                    cands.push(cut);
+                }
+            }
+        }
+
+        cands
+            .into_iter()
+            .filter_map(|(p, q)| validate_cut(input, p, q))
+            .collect()
+    }
+}
+
+// ax + by = gcd(a, b) となる (gcd, x, y)
+fn ext_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = ext_gcd(b, a % b);
+        (g, y, x - (a / b) * y)
+    }
+}
+
+// 半回転を num 等分した向き (offset はラジアン)。整数ベクトルで既約にしてある
+fn sweep_dirs(num: usize, offset: f64) -> Vec<(i64, i64)> {
+    let mut dirs = vec![];
+    for k in 0..num {
+        let theta = offset + std::f64::consts::PI * k as f64 / num as f64;
+        let ux = (SPLIT_DIR_SCALE * theta.cos()).round() as i64;
+        let uy = (SPLIT_DIR_SCALE * theta.sin()).round() as i64;
+        let (g, _, _) = ext_gcd(ux.abs(), uy.abs());
+        if g != 0 {
+            dirs.push((ux / g, uy / g));
+        }
+    }
+
+    dirs
+}
+
+// ピースから t 個をちょうど切り出す直線を探す。
+// 各向き u について射影の下位 t 個 / 上位 t 個を分ける u・s = c (c は整数) を作り、
+// どの苺 (他ピースのものも含む) も通らないもののうち、最も余裕のある直線を返す
+fn find_split_line(
+    input: &Input,
+    piece: &[usize],
+    t: usize,
+    dirs: &[(i64, i64)],
+) -> Option<(Coord, Coord)> {
+    if t == 0 || t >= piece.len() {
+        return None;
+    }
+
+    let mut best: Option<(f64, (Coord, Coord))> = None;
+    for &(ux, uy) in dirs {
+        let proj_of = |j: usize| {
+            let (x, y) = input.xy[j];
+            ux * x as i64 + uy * y as i64
+        };
+        let mut proj = piece.iter().map(|&j| proj_of(j)).collect::<Vec<_>>();
+        proj.sort();
+
+        // 下位 t 個 と 上位 t 個 (= 逆向きの下位 t 個)
+        for &k in &[t, piece.len() - t] {
+            let (lo, hi) = (proj[k - 1], proj[k]);
+            if hi - lo < 2 {
+                continue;
+            }
+
+            // 間に射影される苺があれば避ける
+            let blocked = (0..input.n)
+                .map(proj_of)
+                .filter(|&v| lo < v && v < hi)
+                .collect::<HashSet<_>>();
+            let mid = lo + (hi - lo) / 2;
+            let c = (0..(hi - lo))
+                .flat_map(|d| vec![mid - d, mid + d])
+                .find(|&c| lo < c && c < hi && !blocked.contains(&c));
+            let c = match c {
+                Some(c) => c,
+                None => continue,
+            };
+
+            let margin = (c - lo).min(hi - c) as f64 / ((ux * ux + uy * uy) as f64).sqrt();
+            if matches!(best, Some((m, _)) if m >= margin) {
+                continue;
+            }
+
+            // u・p = c となる格子点のうち原点に近いもの
+            // 既約なので g = ±1
+            let (g, gx, gy) = ext_gcd(ux, uy);
+            let (x0, y0) = (gx * g * c, gy * g * c);
+            let norm = (ux * ux + uy * uy) as f64;
+            let k = ((-(x0 as f64) * uy as f64 + y0 as f64 * ux as f64) / norm).round() as i64;
+            let p = Coord::new(((x0 + k * uy) as isize, (y0 - k * ux) as isize));
+            // 左側 (side > 0) が u・s < c になる向き
+            let q = p.plus(&Coord::new((-uy as isize, ux as isize)));
+            if !in_bounds(&p) || !in_bounds(&q) {
+                continue;
+            }
+
+            best = Some((margin, (p, q)));
+        }
+    }
+
+    best.map(|(_, cut)| cut)
 }
 
 #[fastout]
 fn main() {
-    let system_time = SystemTime::now();
-    let mut rng = thread_rng();
+    let params = Params::from_env();
+    for name in params.unknown(&PARAM_NAMES) {
+        eprintln!("unknown param: {}", name);
+    }
+    let timer = Timer::new(params.get("limit_time", LIMIT_TIME))
+        .margin(params.get("limit_margin", LIMIT_MARGIN));
+    let k: usize = params.get("k", K).min(K); // 問題の上限を超えない
+    let evaluator = params.get("evaluator", EVALUATOR);
+    let mut rng = Rng::from_time();
 
     input! {
         n: usize, // マックス5,500?
@@ -161,72 +625,94 @@ fn main() {
         xy: [(isize, isize); n],
     }
 
-    let input = Input { n, a, xy };
+    let input = Input {
+        n,
+        a,
+        xy,
+        evaluator,
+    };
 
-    let mut best_score = 10000000000;
+    let mut beam = vec![BeamState::new(&input)];
+    let mut best_score = compute_score(&input, &beam[0].b);
     let mut best_out = vec![];
 
-    while system_time.elapsed().unwrap().as_millis() < (LIMIT_TIME - 100) as u128 {
-        let mut out = Vec::with_capacity(K);
-        for i in 0..=K {
-            if i == 0 {
-                let px = rng.gen_range(-RADIUS, RADIUS);
-                let py = rng.gen_range(-RADIUS, RADIUS);
-                let qx = rng.gen_range(-RADIUS, RADIUS);
-                let qy = rng.gen_range(-RADIUS, RADIUS);
-                let p = Coord::new((px, py));
-                let q = Coord::new((qx, qy));
-
-                out.push((p, q));
-            } else {
-                let (b, piece_a, piece_b) = make_b(&input, &out);
-                let score = compute_shortage(&input, &b);
-                if score < best_score {
-                    // ベストスコアの更新
-                    best_score = score;
-                    best_out = out.clone();
-                }
+    let mut width = BEAM_INIT_WIDTH;
+    for depth in 0..k {
+        let depth_start = timer.elapsed_us();
 
-                if i == K {
-                    break;
+        let mut next = vec![];
+        for st in &beam {
+            if timer.is_over() {
+                break;
+            }
+            for cut in st.candidates(&input, &mut rng) {
+                if let Some(child) = st.apply(&input, cut) {
+                    next.push(child);
                 }
-
-                let p = central_point(&input, &piece_a);
-                let q = central_point(&input, &piece_b);
-
-                out.push((p, q))
             }
+        }
+        if next.is_empty() {
+            break;
+        }
 
-            if system_time.elapsed().unwrap().as_millis() > (LIMIT_TIME - 100) as u128 {
-                break;
+        next.sort_by(|s1, s2| s1.eval.partial_cmp(&s2.eval).unwrap());
+        // b とピース数が同じ状態は評価も同じなので1つだけ残す
+        let mut seen = HashSet::new();
+        next.retain(|st| seen.insert((st.b.clone(), st.pieces.len())));
+        next.truncate(width);
+
+        for st in &next {
+            let score = compute_score(&input, &st.b);
+            if score > best_score {
+                // ベストスコアの更新
+                best_score = score;
+                best_out = st.out.clone();
             }
         }
+
+        // 残り時間に収まるようにビーム幅を調整
+        // 1深さが 1ms に満たないこともあるので µs で測る
+        let now = timer.elapsed_us();
+        let limit = timer.limit() * 1000;
+        if now >= limit {
+            break;
+        }
+        let per_state = (now - depth_start) as f64 / beam.len() as f64;
+        let remaining = (limit - now) as f64;
+        let remaining_depth = (k - depth - 1).max(1) as f64;
+        width = ((remaining / remaining_depth / per_state.max(1.0)) as usize)
+            .clamp(BEAM_MIN_WIDTH, BEAM_MAX_WIDTH);
+
+        beam = next;
     }
 
     // output
+    // 不正なカットは提出しない
+    best_out.retain(|cut| is_valid_cut(&input, cut));
+    let cut_num = best_out.len();
+    let best_out = remove_redundant_cuts(&input, &best_out);
+    eprintln!("redundant cuts: {}", cut_num - best_out.len());
+    let (b, _) = make_b(&input, &best_out);
+    eprintln!("score: {}", compute_score(&input, &b));
+    // AHC012_PIECES=path でピースの形を書き出す
+    if let Ok(path) = std::env::var("AHC012_PIECES") {
+        if let Err(e) = write_pieces(&path, &make_pieces(&input, &best_out)) {
+            eprintln!("cannot write {}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
     print_out(&best_out);
 
-    eprintln!("{}ms", system_time.elapsed().unwrap().as_millis());
+    eprintln!("{}ms", timer.elapsed());
 }
 
 fn print_out(out: &Vec<(Coord, Coord)>) {
     println!("{}", out.len());
     for (p, q) in out {
-        println!("{} {}", p.to_str(), q.to_str());
+        println!("{} {} {} {}", p.x, p.y, q.x, q.y);
     }
 }
 
-fn make_random_2p(rng: &mut ThreadRng) -> (Coord, Coord) {
-    let px = rng.gen_range(-RADIUS, RADIUS);
-    let py = rng.gen_range(-RADIUS, RADIUS);
-    let qx = rng.gen_range(-RADIUS, RADIUS);
-    let qy = rng.gen_range(-RADIUS, RADIUS);
-    let p = Coord::new((px, py));
-    let q = Coord::new((qx, qy));
-
-    (p, q)
-}
-
 fn central_point(input: &Input, piece: &Vec<usize>) -> Coord {
     let mut acc_x = 0;
     let mut acc_y = 0;
@@ -243,13 +729,167 @@ fn central_point(input: &Input, piece: &Vec<usize>) -> Coord {
     Coord::new((nx, ny))
 }
 
-fn eval(input: &Input, b: &Vec<usize>) -> f64 {
+fn eval(input: &Input, b: &[usize]) -> f64 {
     let mut res = 0.0;
-    for d in 3..10 {
-        if b[d] < input.a[d] {
-            res += (input.a[d] - b[d]) as f64 * (d - 2) as f64 * 0.3;
+    for (d, (&a, &b)) in input.a.iter().zip(b).enumerate().skip(3) {
+        if b < a {
+            res += (a - b) as f64 * (d - 2) as f64 * 0.3;
         }
     }
 
     res
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_input(xy: Vec<(isize, isize)>) -> Input {
+        Input {
+            n: xy.len(),
+            a: vec![1; 10],
+            xy,
+            evaluator: EVALUATOR,
+        }
+    }
+
+    fn cut((px, py): (isize, isize), (qx, qy): (isize, isize)) -> (Coord, Coord) {
+        (Coord::new((px, py)), Coord::new((qx, qy)))
+    }
+
+    #[test]
+    fn validate_cut_repairs_point_and_strawberry() {
+        let input = make_input(vec![(0, 0), (3, 5)]);
+
+        // p == q は直線にする
+        let c = validate_cut(&input, Coord::new((10, 10)), Coord::new((10, 10))).unwrap();
+        assert_ne!(c.0, c.1);
+        assert!(is_valid_cut(&input, &c));
+
+        // 苺 (0, 0) を通る直線はずらして避ける
+        let p = Coord::new((-7, -7));
+        let q = Coord::new((7, 7));
+        assert!(!is_valid_cut(&input, &(p, q)));
+        let c = validate_cut(&input, p, q).unwrap();
+        assert!(is_valid_cut(&input, &c));
+        assert_eq!(c.1.minus(&c.0), q.minus(&p));
+    }
+
+    #[test]
+    fn validate_cut_rejects_out_of_range() {
+        let input = make_input(vec![(0, 0)]);
+        let far = Coord::new((COORD_LIMIT + 1, 0));
+        assert_eq!(validate_cut(&input, far, Coord::new((1, 1))), None);
+        assert!(!is_valid_cut(&input, &(Coord::new((1, 1)), far)));
+
+        // 範囲の端で p == q でも範囲内に直す
+        let edge = Coord::new((COORD_LIMIT, COORD_LIMIT));
+        let c = validate_cut(&input, edge, edge).unwrap();
+        assert!(is_valid_cut(&input, &c));
+    }
+
+    #[test]
+    fn find_split_line_cuts_exactly_d() {
+        let mut rng = Rng::new(1);
+        let mut xy = vec![];
+        let mut seen = HashSet::new();
+        while xy.len() < 200 {
+            let x = rng.gen_range_i64(-RADIUS as i64 / 2, RADIUS as i64 / 2) as isize;
+            let y = rng.gen_range_i64(-RADIUS as i64 / 2, RADIUS as i64 / 2) as isize;
+            if seen.insert((x, y)) {
+                xy.push((x, y));
+            }
+        }
+        let input = make_input(xy);
+        // 半分のピースから切り出す。他のピースの苺も線上に来てはいけない
+        let piece = (0..100).collect::<Vec<_>>();
+        let dirs = sweep_dirs(SPLIT_DIR_NUM, 0.3);
+        for d in 1..=10 {
+            let c = find_split_line(&input, &piece, d, &dirs).unwrap();
+            assert!(is_valid_cut(&input, &c), "d = {}", d);
+            let (left, right) = split_piece(&input, &piece, &c);
+            assert_eq!(left.len() + right.len(), piece.len());
+            assert!(
+                left.len() == d || right.len() == d,
+                "d = {}: {} / {}",
+                d,
+                left.len(),
+                right.len()
+            );
+        }
+
+        assert_eq!(find_split_line(&input, &piece, 0, &dirs), None);
+        assert_eq!(find_split_line(&input, &piece, piece.len(), &dirs), None);
+    }
+
+    #[test]
+    fn split_conserves_area() {
+        let disk = Polygon::disk();
+        let cuts = vec![
+            cut((0, 0), (1, 0)),
+            cut((-3_000, 7_000), (5_000, -2_000)),
+            cut((9_000, -20_000), (9_000, 20_000)),
+        ];
+        for c in &cuts {
+            let (left, right) = disk.split(c);
+            let total = left.area() + right.area();
+            assert!((total - disk.area()).abs() < 1e-6 * disk.area(), "{:?}", c);
+        }
+
+        // 続けて切っても総面積は変わらない
+        let mut polygons = vec![disk.clone()];
+        for c in &cuts {
+            polygons = polygons
+                .iter()
+                .flat_map(|pg| {
+                    let (l, r) = pg.split(c);
+                    vec![l, r]
+                })
+                .collect();
+        }
+        let total = polygons.iter().map(|pg| pg.area()).sum::<f64>();
+        assert!((total - disk.area()).abs() < 1e-6 * disk.area());
+    }
+
+    #[test]
+    fn make_pieces_places_strawberries_in_their_polygon() {
+        let input = make_input(vec![(100, 100), (-100, 100), (-100, -100), (5_000, 5_000)]);
+        let out = vec![cut((0, -1), (0, 1)), cut((-1, 0), (1, 0))];
+        let pieces = make_pieces(&input, &out);
+        assert_eq!(pieces.len(), 4);
+
+        let mut sizes = pieces.iter().map(|(e, _)| e.len()).collect::<Vec<_>>();
+        sizes.sort();
+        assert_eq!(sizes, vec![0, 1, 1, 2]);
+        for (piece, polygon) in &pieces {
+            // 四分円の重心は苺と同じ象限にある
+            let (cx, cy) = polygon.centroid().unwrap();
+            for &j in piece {
+                let (x, y) = input.xy[j];
+                assert!(
+                    cx * x as f64 > 0.0 && cy * y as f64 > 0.0,
+                    "{} {:?}",
+                    j,
+                    (cx, cy)
+                );
+            }
+        }
+
+        let (cx, cy) = Polygon::disk().centroid().unwrap();
+        assert!(cx.abs() < 1e-6 && cy.abs() < 1e-6);
+    }
+
+    #[test]
+    fn remove_redundant_cuts_keeps_b() {
+        let input = make_input(vec![(-5, -5), (-5, 5), (5, -5), (5, 5), (1, 2)]);
+        let out = vec![
+            cut((0, -1), (1, 1)),     // 縦にほぼ 2 分割
+            cut((-100, 0), (-99, 1)), // 苺を分けない
+            cut((-1, 0), (1, 1)),     // 横にほぼ 2 分割
+            cut((0, -1), (1, 1)),     // 1 本目と同じ
+        ];
+        let reduced = remove_redundant_cuts(&input, &out);
+        assert!(reduced.len() < out.len());
+        assert_eq!(make_b(&input, &reduced).0, make_b(&input, &out).0);
+    }
+}
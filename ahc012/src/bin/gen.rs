@@ -0,0 +1,183 @@
+// AHC012 の入力生成器
+// Usage: gen <seed_from> <seed_to> [--dir DIR] [--n N] [--a SHAPE] [--overwrite]
+//   DIR   : 出力先 (default: tools/in)。 {seed:04}.txt に書き出す
+//           既存のファイルは --overwrite が無ければ上書きせず、飛ばした seed を表示する
+//   N     : 苺の数を固定する (default: sum(d * a_d))
+//   SHAPE : a の分布
+//           uniform  各 a_d を [1, 100] から一様に (default)
+//           small    小さいピースほど多く要求する
+//           large    大きいピースほど多く要求する
+//           const:V  全て V
+
+use heuristic_core::rng::Rng;
+use std::collections::HashSet;
+use std::io::Write;
+
+const K: usize = 100;
+const RADIUS: i64 = 10_000;
+const A_MAX: usize = 100;
+
+enum Shape {
+    Uniform,
+    Small,
+    Large,
+    Const(usize),
+}
+
+impl Shape {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "uniform" => Some(Shape::Uniform),
+            "small" => Some(Shape::Small),
+            "large" => Some(Shape::Large),
+            _ => {
+                let v = s.strip_prefix("const:")?.parse().ok()?;
+                if (1..=A_MAX).contains(&v) {
+                    Some(Shape::Const(v))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    // a_1..a_10 を生成する
+    fn gen_a(&self, rng: &mut Rng) -> Vec<usize> {
+        (1..=10)
+            .map(|d| match self {
+                Shape::Uniform => rng.gen_range(1, A_MAX + 1),
+                Shape::Small => (rng.gen_range(1, A_MAX + 1) * (11 - d) / 10).max(1),
+                Shape::Large => (rng.gen_range(1, A_MAX + 1) * d / 10).max(1),
+                Shape::Const(v) => *v,
+            })
+            .collect()
+    }
+}
+
+struct Config {
+    dir: String,
+    n: Option<usize>,
+    shape: Shape,
+    overwrite: bool,
+}
+
+// 苺を置ける円内の格子点の数
+fn lattice_point_num() -> usize {
+    let mut num = 0;
+    for x in -RADIUS + 1..RADIUS {
+        // y * y < r2 となる最大の y
+        let r2 = RADIUS * RADIUS - x * x;
+        let mut y = (r2 as f64).sqrt() as i64;
+        while y * y >= r2 {
+            y -= 1;
+        }
+        while (y + 1) * (y + 1) < r2 {
+            y += 1;
+        }
+        num += 2 * y as usize + 1;
+    }
+
+    num
+}
+
+// 円内の相異なる格子点に苺を置く
+fn gen(seed: u64, config: &Config) -> String {
+    let mut rng = Rng::new(seed);
+
+    let a = config.shape.gen_a(&mut rng);
+    let n = config
+        .n
+        .unwrap_or_else(|| a.iter().enumerate().map(|(d, a)| (d + 1) * a).sum());
+
+    let mut used = HashSet::new();
+    let mut xy = Vec::with_capacity(n);
+    while xy.len() < n {
+        let x = rng.gen_range_i64(-RADIUS + 1, RADIUS);
+        let y = rng.gen_range_i64(-RADIUS + 1, RADIUS);
+        if x * x + y * y < RADIUS * RADIUS && used.insert((x, y)) {
+            xy.push((x, y));
+        }
+    }
+
+    let mut s = format!("{} {}\n", n, K);
+    s += &a
+        .iter()
+        .map(|a| a.to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    s += "\n";
+    for (x, y) in xy {
+        s += &format!("{} {}\n", x, y);
+    }
+
+    s
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: {} <seed_from> <seed_to> [--dir DIR] [--n N] [--a uniform|small|large|const:V] [--overwrite]",
+        std::env::args().next().unwrap()
+    );
+    std::process::exit(1)
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.len() < 2 {
+        usage();
+    }
+    let seed_from: u64 = args[0].parse().unwrap_or_else(|_| usage());
+    let seed_to: u64 = args[1].parse().unwrap_or_else(|_| usage());
+
+    let mut config = Config {
+        dir: String::from("tools/in"),
+        n: None,
+        shape: Shape::Uniform,
+        overwrite: false,
+    };
+    let mut i = 2;
+    while i < args.len() {
+        if args[i] == "--overwrite" {
+            config.overwrite = true;
+            i += 1;
+            continue;
+        }
+        let value = args.get(i + 1).unwrap_or_else(|| usage());
+        match args[i].as_str() {
+            "--dir" => config.dir = value.clone(),
+            "--n" => config.n = Some(value.parse().unwrap_or_else(|_| usage())),
+            "--a" => config.shape = Shape::parse(value).unwrap_or_else(|| usage()),
+            _ => usage(),
+        }
+        i += 2;
+    }
+    if let Some(n) = config.n {
+        // 置き場所が足りないと終わらないので先に弾く
+        let max_n = lattice_point_num();
+        if n > max_n {
+            eprintln!(
+                "--n {} is larger than the number of lattice points in the cake ({})",
+                n, max_n
+            );
+            std::process::exit(1);
+        }
+    }
+
+    std::fs::create_dir_all(&config.dir).unwrap();
+    let mut skipped = vec![];
+    for seed in seed_from..=seed_to {
+        let path = format!("{}/{:04}.txt", config.dir, seed);
+        if !config.overwrite && std::path::Path::new(&path).exists() {
+            skipped.push(seed.to_string());
+            continue;
+        }
+        let mut f = std::fs::File::create(&path).unwrap();
+        f.write_all(gen(seed, &config).as_bytes()).unwrap();
+    }
+    if !skipped.is_empty() {
+        eprintln!(
+            "skipped existing seeds (use --overwrite to regenerate): {}",
+            skipped.join(" ")
+        );
+    }
+}
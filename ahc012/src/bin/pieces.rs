@@ -0,0 +1,169 @@
+// AHC012 のピースを塗り分けた SVG を作る
+// Usage: pieces <input> <pieces> [out.svg]
+//   pieces は ahc012 (src/bin/a.rs) を AHC012_PIECES=path 付きで動かして書き出したもの。
+//   苺の数が 1..=10 のピースは緑、多すぎるピースは赤、苺の無いピースは灰色で塗り、重心に苺の数を書く
+
+use heuristic_core::movie::Graphics;
+use std::fs;
+use std::io::Write;
+
+const SIZE: f64 = 800.0; // 画面の一辺
+const VIEW_RADIUS: f64 = 10_100.0; // 画面に収める範囲 (ケーキの半径 + 余白)
+const TEXT_SIZE: f64 = 12.0;
+
+// ピース1つ分
+struct Piece {
+    count: usize,
+    centroid: (f64, f64),
+    vertices: Vec<(f64, f64)>,
+}
+
+fn parse_numbers<T: std::str::FromStr>(line: &str) -> Result<Vec<T>, String> {
+    line.split_whitespace()
+        .map(|e| e.parse().map_err(|_| format!("invalid number: {}", e)))
+        .collect()
+}
+
+// 入力から苺の座標を読む (先頭行 "N K"、次に a の行、続いて N 行の座標)
+fn parse_input(s: &str) -> Result<Vec<(f64, f64)>, String> {
+    let mut lines = s.lines();
+    let head = parse_numbers::<usize>(lines.next().ok_or("empty input")?)?;
+    let n = *head.first().ok_or("missing N")?;
+    lines.next().ok_or("missing a")?;
+    let mut xy = Vec::with_capacity(n);
+    for _ in 0..n {
+        match parse_numbers::<f64>(lines.next().ok_or("missing strawberry")?)?[..] {
+            [x, y] => xy.push((x, y)),
+            _ => return Err("expected x y".to_string()),
+        }
+    }
+
+    Ok(xy)
+}
+
+fn parse_pieces(s: &str) -> Result<Vec<Piece>, String> {
+    let mut pieces = vec![];
+    for line in s.lines() {
+        let v = parse_numbers::<f64>(line)?;
+        if v.len() < 3 + 6 || v.len() % 2 == 0 {
+            return Err(format!("invalid piece: {}", line));
+        }
+        pieces.push(Piece {
+            count: v[0] as usize,
+            centroid: (v[1], v[2]),
+            vertices: v[3..].chunks(2).map(|c| (c[0], c[1])).collect(),
+        });
+    }
+
+    Ok(pieces)
+}
+
+// ケーキの座標 (y は上向き) から画面の座標へ
+fn to_screen((x, y): (f64, f64)) -> (f64, f64) {
+    let scale = SIZE / (2.0 * VIEW_RADIUS);
+    ((x + VIEW_RADIUS) * scale, (VIEW_RADIUS - y) * scale)
+}
+
+fn draw(g: &mut Graphics, xy: &[(f64, f64)], pieces: &[Piece]) {
+    g.screen(SIZE, SIZE);
+    g.clear();
+
+    g.stroke_rgb(0.3, 0.3, 0.3);
+    for piece in pieces {
+        match piece.count {
+            0 => g.fill_rgb(0.85, 0.85, 0.85),
+            1..=10 => g.fill_rgb(0.6, 0.85, 0.6),
+            _ => g.fill_rgb(0.95, 0.55, 0.55),
+        }
+        let points = piece
+            .vertices
+            .iter()
+            .map(|&v| to_screen(v))
+            .collect::<Vec<_>>();
+        g.polygon(&points);
+    }
+
+    // 苺
+    g.no_stroke();
+    g.fill_rgb(0.8, 0.0, 0.0);
+    for &p in xy {
+        let (x, y) = to_screen(p);
+        g.rect(x - 1.0, y - 1.0, 2.0, 2.0);
+    }
+
+    g.fill_rgb(0.0, 0.0, 0.0);
+    for piece in pieces.iter().filter(|e| e.count > 0) {
+        let (x, y) = to_screen(piece.centroid);
+        g.text(
+            format!("{}", piece.count),
+            x,
+            y + TEXT_SIZE / 2.0,
+            TEXT_SIZE,
+        );
+    }
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: {} <input> <pieces> [out.svg]",
+        std::env::args().next().unwrap()
+    );
+    std::process::exit(1)
+}
+
+fn read(path: &str) -> String {
+    fs::read_to_string(path).unwrap_or_else(|_| {
+        eprintln!("no such file: {}", path);
+        std::process::exit(1)
+    })
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.len() < 2 {
+        usage();
+    }
+    let out_path = args.get(2).map(|s| s.as_str()).unwrap_or("pieces.svg");
+
+    let parsed = parse_input(&read(&args[0])).and_then(|xy| {
+        let pieces = parse_pieces(&read(&args[1]))?;
+        Ok((xy, pieces))
+    });
+    let (xy, pieces) = parsed.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    });
+
+    let mut g = Graphics::new();
+    draw(&mut g, &xy, &pieces);
+
+    let mut f = fs::File::create(out_path).unwrap();
+    f.write_all(g.dump(None, None).as_bytes()).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_pieces_reads_centroid_and_vertices() {
+        let pieces = parse_pieces("2 1.0 -1.0 0 0 3 0 0 -3\n").unwrap();
+        assert_eq!(pieces.len(), 1);
+        assert_eq!(pieces[0].count, 2);
+        assert_eq!(pieces[0].centroid, (1.0, -1.0));
+        assert_eq!(
+            pieces[0].vertices,
+            vec![(0.0, 0.0), (3.0, 0.0), (0.0, -3.0)]
+        );
+
+        assert!(parse_pieces("2 1.0 -1.0 0 0 3 0 0\n").is_err());
+        assert!(parse_pieces("2 1.0 -1.0 0 0 3 0 x -3\n").is_err());
+    }
+
+    #[test]
+    fn parse_input_reads_strawberries() {
+        let xy = parse_input("2 100\n1 0 0 0 0 0 0 0 0 0\n10 -20\n-30 40\n").unwrap();
+        assert_eq!(xy, vec![(10.0, -20.0), (-30.0, 40.0)]);
+        assert!(parse_input("3 100\n1 0 0 0 0 0 0 0 0 0\n10 -20\n").is_err());
+    }
+}
@@ -3,39 +3,123 @@ use std::cmp::*;
 #[allow(unused_imports)]
 use std::collections::*;
 
-#[allow(unused_imports)]
-use rand::rngs::ThreadRng;
 #[allow(unused_imports)]
 use rand::seq::SliceRandom;
 #[allow(unused_imports)]
-use rand::{thread_rng, Rng};
+use rand::Rng;
 #[allow(unused_imports)]
 use std::io::Write;
-use std::time::SystemTime;
-
-macro_rules! mat {
-	($($e:expr),*) => { Vec::from(vec![$($e),*]) };
-	($($e:expr,)*) => { Vec::from(vec![$($e),*]) };
-	($e:expr; $d:expr) => { Vec::from(vec![$e; $d]) };
-	($e:expr; $d:expr $(; $ds:expr)+) => { Vec::from(vec![mat![$e $(; $ds)*]; $d]) };
-}
+use std::time::Instant;
 
-const TURN: usize = 100;
-const N: usize = 10;
-const CANDY_KIND: usize = 3;
-const DIRS: [char; 4] = ['F', 'B', 'L', 'R'];
-const DIJ: [(usize, usize); 4] = [(1, 0), (0, 1), (!0, 0), (0, !0)];
+// 提出時は heuristic-core の bundle で1ファイルにまとめる
+use ahc015::game::{gen_input, parse_input, State, CANDY_KIND, COL_LAST, DIRS, FULL, N, TURN};
+use heuristic_core::beam::{Beam, BeamSearch};
+use heuristic_core::io::IO;
+use heuristic_core::mcts::{Budget, Game, Mcts, Phase, Rollout};
+use heuristic_core::params::Params;
+use heuristic_core::rng::Rng as CoreRng;
+use heuristic_core::stats::Paired;
+use heuristic_core::timer::Timer;
 
 const LAST_SEARCH_TURN: usize = 93;
-const SINGLE_PLAYOUT_NUM: usize = 130;
+const TIME_LIMIT: u128 = 2_000; // ms
+const TIME_MARGIN: u128 = 200; // ms
+const MIDGAME_WEIGHT: f64 = 1.0; // 中盤に時間を多めに割り振る度合い (0 なら均等)
+const UCB_C: f64 = 0.05; // 探索項の係数 (スコアは理論上の最大値で正規化する)
+const UCB_MIN_PLAYOUT: usize = 8; // 最初に各方向で回す数
+const UCB_STOP_Z: f64 = 3.0; // 最善手が他より z 標準誤差以上良ければ打ち切る
+const UCB_CHECK_INTERVAL: usize = 16; // 時計と打ち切りを確認する間隔 (プレイアウト数)
+const MCTS_C: f64 = 0.05; // search=mcts のときの UCT の係数 (UCB_C と同じ尺度)
+const MCTS_DEPTH: usize = 2; // 木に含める手の数 (p が 100 通りに分かれるので深くは伸びない)
+const MCTS_EXPAND_AFTER: usize = 16; // この回数訪れたノードから子を作る
+const BENCH_PLAYOUT_NUM: usize = 1000; // --bench で速さを測るプレイアウト数 (seed ごと)
+const COMPARE_CLOCK_STEP: f64 = 0.1; // --compare の偽の時計が1回読むごとに進む ms (本番の速さくらい)
+const OFFLINE_BEAM_WIDTH: usize = 3000; // --offline で p を全部知っているときのビーム幅
+const EXPECTIMAX_DEPTH: usize = 6; // 反復深化の最大の深さ。LAST_SEARCH_TURN より後は最後まで読み切れる
+const PLAYOUT_HORIZON: usize = 30; // プレイアウトを打ち切って evaluate で代える手数 (TURN なら最後まで)
+const PLAN_SIZE_WEIGHT: f64 = 1.0; // 配置計画で領域の大きさと個数のずれに掛ける重み
+const EVAL_WEIGHTS: EvalWeights = EvalWeights {
+    score: 1.0,
+    adjacency: 2.0,
+    distance: 1.0,
+    boundary: 1.0,
+    merge: 0.5,
+};
+const POLICY: &str = "scripted"; // プレイアウトの方策 (parse_policy の書式)
+const SEARCH: Search = Search::Ucb;
+// PARAMS / PARAMS_FILE で変えられるもの (既定値は上の定数)
+const PARAM_NAMES: [&str; 13] = [
+    "policy",
+    "search",
+    "last_search_turn",
+    "midgame_weight",
+    "ucb_c",
+    "ucb_stop_z",
+    "expectimax_depth",
+    "playout_horizon",
+    "eval_score",
+    "eval_adjacency",
+    "eval_distance",
+    "eval_boundary",
+    "eval_merge",
+];
+
+// 探索の調整用の値
+#[derive(Clone, Copy, Debug)]
+struct SearchParams {
+    search: Search,
+    last_search_turn: usize,
+    midgame_weight: f64,
+    ucb_c: f64,
+    ucb_stop_z: f64,
+    expectimax_depth: usize,
+    playout_horizon: usize,
+}
+impl SearchParams {
+    fn new(params: &Params) -> Self {
+        Self {
+            search: params.get("search", SEARCH),
+            last_search_turn: params.get("last_search_turn", LAST_SEARCH_TURN),
+            midgame_weight: params.get("midgame_weight", MIDGAME_WEIGHT),
+            ucb_c: params.get("ucb_c", UCB_C),
+            ucb_stop_z: params.get("ucb_stop_z", UCB_STOP_Z),
+            expectimax_depth: params.get("expectimax_depth", EXPECTIMAX_DEPTH),
+            playout_horizon: params.get("playout_horizon", PLAYOUT_HORIZON),
+        }
+    }
+}
+
+// 盤面評価の特徴量の重み
+#[derive(Clone, Copy, Debug)]
+struct EvalWeights {
+    score: f64,     // compute_score
+    adjacency: f64, // 同じ種類が隣り合っている組の数
+    distance: f64,  // 各キャンディから担当領域までの距離の和 (引く)
+    boundary: f64,  // 違う種類が隣り合っている組の数 (引く)
+    merge: f64,     // 1回傾けたときの compute_score の増分の最大
+}
+impl EvalWeights {
+    // 指定された重みだけ置き換える
+    fn with_params(self, params: &Params) -> Self {
+        Self {
+            score: params.get("eval_score", self.score),
+            adjacency: params.get("eval_adjacency", self.adjacency),
+            distance: params.get("eval_distance", self.distance),
+            boundary: params.get("eval_boundary", self.boundary),
+            merge: params.get("eval_merge", self.merge),
+        }
+    }
+}
 
 struct Input {
     fs: Vec<usize>,         // i番目に置かれるキャンディの種類
     candy_nums: Vec<usize>, // キャンディの種類ごとの個数
     ps: Vec<usize>,         // 置かれる位置
 
-    candy1: usize, // 数が１番多いキャンディ
-    candy2: usize, // その他から乱択
+    plan: Plan,                   // 種類ごとの担当領域
+    region_dist: Vec<Vec<usize>>, // [種類 - 1][マス]: 領域までのマンハッタン距離
+    eval_weights: EvalWeights,
+    search_params: SearchParams,
 }
 impl Input {
     fn new(f: Vec<usize>) -> Self {
@@ -44,261 +128,1134 @@ impl Input {
             candy_nums[*e] += 1;
         }
 
+        let plan = Plan::new(&candy_nums);
         let mut input = Self {
             fs: f,
             candy_nums,
             ps: vec![],
 
-            candy1: 0,
-            candy2: 0,
+            plan: plan.clone(),
+            region_dist: vec![],
+            eval_weights: EVAL_WEIGHTS,
+            search_params: SearchParams::new(&Params::default()),
         };
-
-        let candy1 = input.max_num_candy();
-        let candy2 = (candy1 % CANDY_KIND) + 1;
-        input.candy1 = candy1;
-        input.candy2 = candy2;
+        input.set_plan(plan);
 
         input
     }
 
-    // 一番数が多いキャンディの種類番号を返す
-    fn max_num_candy(&self) -> usize {
-        let mut hoge = 0;
-        let mut res = 0;
+    fn set_plan(&mut self, plan: Plan) {
+        self.region_dist = plan
+            .regions
+            .iter()
+            .map(|&region| {
+                (0..N * N)
+                    .map(|b| {
+                        (0..N * N)
+                            .filter(|&r| region >> r & 1 == 1)
+                            .map(|r| {
+                                let di = (b / N) as isize - (r / N) as isize;
+                                let dj = (b % N) as isize - (r % N) as isize;
+                                (di.abs() + dj.abs()) as usize
+                            })
+                            .min()
+                            .unwrap_or(0)
+                    })
+                    .collect()
+            })
+            .collect();
+        self.plan = plan;
+    }
 
-        for i in 1..=CANDY_KIND {
-            if hoge < self.candy_nums[i] {
-                hoge = self.candy_nums[i];
-                res = i;
+    // スコアの分母 (キャンディの種類ごとの個数の二乗和)
+    fn score_den(&self) -> usize {
+        self.candy_nums.iter().map(|c| c * c).sum()
+    }
+}
+
+// 盤面の配置計画: 種類ごとの担当領域と、その種類が次に来るときに寄せる方向
+#[derive(Clone, Debug)]
+struct Plan {
+    regions: [u128; CANDY_KIND],
+    home: [Option<char>; CANDY_KIND], // None: 寄せる方向がない (縞の真ん中)
+    primary: usize,                   // 一番大きい領域の種類。これを寄せた後は一度逆に戻す
+}
+impl Plan {
+    // 候補の配置 (L字 / 縞, 4回転, 種類の割り当て) から、
+    // 領域の境界の長さと個数とのずれが最も小さいものを選ぶ。
+    // primary は一番よく寄せる方向なので、一番数が多い種類に限る
+    fn new(candy_nums: &[usize]) -> Self {
+        let mut best: Option<(f64, Plan)> = None;
+        for perm in &[
+            [1, 2, 3],
+            [1, 3, 2],
+            [2, 1, 3],
+            [2, 3, 1],
+            [3, 1, 2],
+            [3, 2, 1],
+        ] {
+            let counts = perm.iter().map(|&c| candy_nums[c]).collect::<Vec<_>>();
+            if counts[0] < *counts.iter().max().unwrap() {
+                continue;
+            }
+            for &stripe in &[false, true] {
+                let base = Self::base_layout(perm, &counts, stripe);
+                let mut plan = base;
+                for _ in 0..4 {
+                    let cost = plan.cost(candy_nums);
+                    if !matches!(best, Some((c, _)) if c <= cost) {
+                        best = Some((cost, plan.clone()));
+                    }
+                    plan = plan.rotate();
+                }
             }
         }
 
-        res
+        best.unwrap().1
+    }
+
+    // perm[0] を左に寄せた配置
+    // L字: perm[0] が左の列、perm[1] が残りの上側、perm[2] が下側
+    // 縞: 左から perm[0], perm[1], perm[2] の列。真ん中の列へはどちらに傾けても寄せられないので方向なし
+    fn base_layout(perm: &[usize; 3], counts: &[usize], stripe: bool) -> Self {
+        let round = |c: usize, w: usize| (c + w / 2) / w;
+        let cols = round(counts[0], N).clamp(1, N - 1);
+
+        let mut regions = [0; CANDY_KIND];
+        let mut home = [Some('L'); CANDY_KIND];
+        if stripe {
+            let cols2 = round(counts[1], N).min(N - cols);
+            for i in 0..N {
+                for j in 0..N {
+                    let k = if j < cols {
+                        0
+                    } else if j < cols + cols2 {
+                        1
+                    } else {
+                        2
+                    };
+                    regions[perm[k] - 1] |= 1 << (i * N + j);
+                }
+            }
+            home[perm[1] - 1] = None;
+            home[perm[2] - 1] = Some('R');
+        } else {
+            let rows = round(counts[1], N - cols).min(N);
+            for i in 0..N {
+                for j in 0..N {
+                    let k = if j < cols {
+                        0
+                    } else if i < rows {
+                        1
+                    } else {
+                        2
+                    };
+                    regions[perm[k] - 1] |= 1 << (i * N + j);
+                }
+            }
+            home[perm[1] - 1] = Some('F');
+            home[perm[2] - 1] = Some('B');
+        }
+
+        Self {
+            regions,
+            home,
+            primary: perm[0],
+        }
+    }
+
+    // 時計回りに90度回す ((i, j) -> (j, N - 1 - i))
+    fn rotate(&self) -> Self {
+        let mut regions = [0; CANDY_KIND];
+        for (c, &region) in self.regions.iter().enumerate() {
+            for b in 0..N * N {
+                if region >> b & 1 == 1 {
+                    let (i, j) = (b / N, b % N);
+                    regions[c] |= 1 << (j * N + (N - 1 - i));
+                }
+            }
+        }
+        let home = self.home.map(|dir| {
+            dir.map(|dir| match dir {
+                'L' => 'F',
+                'F' => 'R',
+                'R' => 'B',
+                _ => 'L',
+            })
+        });
+
+        Self {
+            regions,
+            home,
+            primary: self.primary,
+        }
+    }
+
+    // 領域の境界の長さ + 個数とのずれ
+    fn cost(&self, candy_nums: &[usize]) -> f64 {
+        let mut boundary = 0;
+        for &region in &self.regions {
+            let other = FULL & !region;
+            boundary += (region & (other >> 1) & !COL_LAST).count_ones();
+            boundary += (region & (other >> N)).count_ones();
+            boundary += (other & (region >> 1) & !COL_LAST).count_ones();
+            boundary += (other & (region >> N)).count_ones();
+        }
+        let mismatch = (1..=CANDY_KIND)
+            .map(|c| (self.regions[c - 1].count_ones() as isize - candy_nums[c] as isize).abs())
+            .sum::<isize>();
+
+        // 境界は両側から数えているので半分にする
+        boundary as f64 / 2.0 + PLAN_SIZE_WEIGHT * mismatch as f64
+    }
+
+    fn opposite(dir: char) -> char {
+        match dir {
+            'L' => 'R',
+            'R' => 'L',
+            'F' => 'B',
+            _ => 'F',
+        }
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct State {
-    pub board: Vec<Vec<usize>>,
-    pub t: usize,
+// 残り時間を残りターンに割り振る
+struct TimeManager {
+    timer: Timer,
+    weights: Vec<f64>, // ターンごとの時間の重み
 }
+impl TimeManager {
+    fn new(timer: Timer, midgame_weight: f64) -> Self {
+        let weights = (0..TURN)
+            .map(|t| {
+                if t == TURN - 1 {
+                    // なに返しても一緒
+                    0.0
+                } else {
+                    1.0 + midgame_weight * (std::f64::consts::PI * t as f64 / TURN as f64).sin()
+                }
+            })
+            .collect();
 
-// 打ち手を返す
-fn simulate(st: &State, input: &Input) -> char {
-    let mut res = 'c';
-    let mut best = 0;
+        Self { timer, weights }
+    }
+
+    fn elapsed(&self) -> u128 {
+        self.timer.elapsed()
+    }
+    fn elapsed_us(&self) -> u128 {
+        self.timer.elapsed_us()
+    }
+
+    // ターン ti の締め切り (開始からの ms)
+    fn deadline(&self, ti: usize) -> u128 {
+        let now = self.elapsed();
+        let remaining = self.timer.limit().saturating_sub(now) as f64;
+        let rest = self.weights[ti..].iter().sum::<f64>();
+        if rest <= 0.0 {
+            return now;
+        }
+
+        now + (remaining * self.weights[ti] / rest) as u128
+    }
+}
+
+// 打ち手を返す。締め切りに間に合う深さまで反復深化する
+// => (方向, 方向ごとの推定スコア)
+fn simulate(
+    st: &State,
+    input: &Input,
+    max_depth: usize,
+    tm: &TimeManager,
+    deadline: u128,
+) -> (char, Vec<(char, f64)>) {
+    // 浅い探索は 1ms もかからないので µs で測る
+    let deadline = deadline.saturating_mul(1000);
+    let mut start = tm.elapsed_us();
+    let mut res = simulate_depth(st, input, 1);
+    for depth in 2..=max_depth.min(TURN - st.t - 1) {
+        // 1段深くすると置き方 × 方向の数だけ増える。深さ1で締め切りを過ぎていたらそこでやめる
+        let now = tm.elapsed_us();
+        let estimate = (now - start) * (4 * (TURN - st.t - depth)) as u128;
+        if now + estimate > deadline {
+            break;
+        }
+        start = now;
+        res = simulate_depth(st, input, depth);
+    }
+
+    res
+}
+// 深さを固定して打ち手を返す (depth: 先読みする置き方の回数)
+fn simulate_depth(st: &State, input: &Input, depth: usize) -> (char, Vec<(char, f64)>) {
+    // evaluate は負にもなるので、どの値より小さいところから始める
+    let mut res = DIRS[0];
+    let mut best = f64::NEG_INFINITY;
+    let mut values = vec![];
 
     for &dir in &DIRS {
-        let mut next_st = st.clone();
+        let mut next_st = *st;
         let _ = next_st.apply_move(dir);
 
-        let hoge = simulate_put(next_st, input);
-        if hoge > best {
-            best = hoge;
+        let value = simulate_put(next_st, input, depth);
+        if value > best {
+            best = value;
             res = dir;
         }
+        values.push((dir, 1e6 * value / input.score_den() as f64));
     }
 
-    res
+    (res, values)
 }
-fn simulate_put(st: State, input: &Input) -> usize {
-    // 全置き方で進めて、最も悪い値を返す
-    let mut acc = std::usize::MAX;
-    for p in 1..=(TURN - st.t) {
-        let mut next_st = st.clone();
-        next_st.apply_put(p, input);
-        let hoge = simulate_move(next_st, input);
-        acc += hoge;
+// 全置き方 (等確率) で進めた値の期待値を返す
+fn simulate_put(st: State, input: &Input, depth: usize) -> f64 {
+    if st.t == TURN {
+        return st.compute_score() as f64;
+    }
+    if depth == 0 {
+        return evaluate(&st, input);
+    }
+
+    let empty = TURN - st.t;
+    let mut acc = 0.0;
+    for p in 1..=empty {
+        let mut next_st = st;
+        next_st.apply_put(p, &input.fs);
+        acc += simulate_move(next_st, input, depth);
     }
 
-    acc / (TURN - st.t)
+    acc / empty as f64
 }
-fn simulate_move(st: State, input: &Input) -> usize {
+// 4パターンのムーブを試して、一番良い値を返す
+fn simulate_move(st: State, input: &Input, depth: usize) -> f64 {
+    // 最後は盤面が埋まっているので傾けても変わらない
     if st.t == TURN - 1 {
-        st.compute_score()
+        return st.compute_score() as f64;
+    }
+
+    let mut best = f64::NEG_INFINITY;
+    for &dir in &DIRS {
+        let mut next_st = st;
+        let _ = next_st.apply_move(dir);
+
+        let value = simulate_put(next_st, input, depth - 1);
+        if value > best {
+            best = value;
+        }
+    }
+
+    best
+}
+
+// 探索の葉やプレイアウトの打ち切りでの盤面評価
+fn evaluate(st: &State, input: &Input) -> f64 {
+    let w = &input.eval_weights;
+    let score = st.compute_score() as f64;
+    let (same, diff) = adjacency(st);
+    let distance = region_distance(st, input) as f64;
+    let merge = if w.merge != 0.0 && st.t < TURN {
+        DIRS.iter()
+            .map(|&dir| {
+                let mut next_st = *st;
+                let _ = next_st.apply_move(dir);
+                next_st.compute_score() as f64 - score
+            })
+            .fold(0.0, f64::max)
+    } else {
+        0.0
+    };
+
+    w.score * score + w.adjacency * same as f64 - w.distance * distance - w.boundary * diff as f64
+        + w.merge * merge
+}
+
+// 隣り合うキャンディの組の数 => (同じ種類, 違う種類)
+fn adjacency(st: &State) -> (usize, usize) {
+    let pairs =
+        |m: u128| ((m & (m >> 1) & !COL_LAST).count_ones() + (m & (m >> N)).count_ones()) as usize;
+    let same = st.masks.iter().map(|&m| pairs(m)).sum::<usize>();
+
+    (same, pairs(st.occupied()) - same)
+}
+
+// 各キャンディから担当領域までの距離の和
+fn region_distance(st: &State, input: &Input) -> usize {
+    let mut acc = 0;
+    for (c, &mask) in st.masks.iter().enumerate() {
+        let mut rest = mask;
+        while rest != 0 {
+            acc += input.region_dist[c][rest.trailing_zeros() as usize];
+            rest &= rest - 1;
+        }
+    }
+
+    acc
+}
+
+fn play(ti: usize, ok_flag: &mut bool, input: &Input) -> char {
+    if ti == TURN - 1 {
+        'R'
     } else {
-        // 4パターンのムーブを試して、一番良い値を返す
+        let plan = &input.plan;
+        let primary = plan.home[plan.primary - 1].unwrap();
+        // 方向のない種類は primary と同じに傾ける (primary の列のすぐ隣に積もる)
+        let home = plan.home[input.fs[ti + 1] - 1].unwrap_or(primary);
+        if home == primary {
+            *ok_flag = false;
+            primary
+        } else {
+            if !(*ok_flag) {
+                *ok_flag = true;
+                Plan::opposite(primary)
+            }
+            // 担当領域の方へ寄せる
+            else {
+                home
+            }
+        }
+    }
+}
+
+// プレイアウトで傾ける方向の決め方
+trait Policy {
+    // ti ターン目に置いた後の盤面 st で傾ける方向
+    fn choose(&self, ti: usize, st: &State, input: &Input, rng: &mut CoreRng) -> char;
+}
+
+// play で事前に決めた手順どおり
+struct ScriptedPolicy {
+    play_list: Vec<char>,
+}
+impl ScriptedPolicy {
+    fn new(input: &Input) -> Self {
+        let mut play_list = vec![];
+        let mut ok_flag = true; // 上下動しても良し
+        for i in 0..TURN {
+            let c = play(i, &mut ok_flag, input);
+            play_list.push(c);
+        }
+
+        Self { play_list }
+    }
+}
+impl Policy for ScriptedPolicy {
+    fn choose(&self, ti: usize, _st: &State, _input: &Input, _rng: &mut CoreRng) -> char {
+        self.play_list[ti]
+    }
+}
+
+// 一様ランダム
+struct RandomPolicy;
+impl Policy for RandomPolicy {
+    fn choose(&self, _ti: usize, _st: &State, _input: &Input, rng: &mut CoreRng) -> char {
+        DIRS[rng.gen_range(0, DIRS.len())]
+    }
+}
+
+// 1手読みで compute_score が最大になる方向
+struct GreedyPolicy;
+impl Policy for GreedyPolicy {
+    fn choose(&self, _ti: usize, st: &State, _input: &Input, _rng: &mut CoreRng) -> char {
         let mut best = 0;
+        let mut res = DIRS[0];
         for &dir in &DIRS {
-            let mut next_st = st.clone();
+            let mut next_st = *st;
             let _ = next_st.apply_move(dir);
+            let score = next_st.compute_score();
+            if score > best {
+                best = score;
+                res = dir;
+            }
+        }
+
+        res
+    }
+}
+
+// 確率 epsilon で一様ランダム、それ以外は base に従う
+struct MixedPolicy {
+    base: Box<dyn Policy>,
+    epsilon: f64,
+}
+impl Policy for MixedPolicy {
+    fn choose(&self, ti: usize, st: &State, input: &Input, rng: &mut CoreRng) -> char {
+        if rng.gen_bool(self.epsilon) {
+            RandomPolicy.choose(ti, st, input, rng)
+        } else {
+            self.base.choose(ti, st, input, rng)
+        }
+    }
+}
 
-            let hoge = simulate_put(next_st, input);
-            if hoge > best {
-                best = hoge;
+// scripted | random | greedy | mixed:EPS[:BASE] (BASE の既定は scripted)
+fn parse_policy(s: &str, input: &Input) -> Option<Box<dyn Policy>> {
+    let args = s.split(':').collect::<Vec<_>>();
+    match args[0] {
+        "scripted" => Some(Box::new(ScriptedPolicy::new(input))),
+        "random" => Some(Box::new(RandomPolicy)),
+        "greedy" => Some(Box::new(GreedyPolicy)),
+        "mixed" => {
+            let epsilon = args.get(1)?.parse::<f64>().ok()?;
+            if !(0.0..=1.0).contains(&epsilon) {
+                return None;
             }
+            let base = if args.len() > 2 {
+                parse_policy(&args[2..].join(":"), input)?
+            } else {
+                Box::new(ScriptedPolicy::new(input))
+            };
+            Some(Box::new(MixedPolicy { base, epsilon }))
         }
+        _ => None,
+    }
+}
+
+// パラメータ policy の方策。読めない値は黙って scripted に戻さず止める
+fn policy_from_params(params: &Params, input: &Input) -> Box<dyn Policy> {
+    let s = params.get("policy", POLICY.to_string());
+    parse_policy(&s, input).unwrap_or_else(|| {
+        eprintln!("invalid value for policy: {}", s);
+        std::process::exit(1)
+    })
+}
+
+// プレイアウトしたスコアを返す。playout_horizon 手で打ち切ったら evaluate の値
+// ti: turn - 1
+fn playout(
+    mut ti: usize,
+    mut st: State,
+    policy: &dyn Policy,
+    input: &Input,
+    rng: &mut CoreRng,
+) -> f64 {
+    let end = TURN.min(ti + input.search_params.playout_horizon);
+    while ti < end {
+        let point = rng.gen_range(1, TURN - ti + 1);
+
+        st.apply_put(point, &input.fs);
+
+        let c = policy.choose(ti, &st, input, rng);
+
+        let _ = st.apply_move(c);
 
-        best
+        ti += 1;
+    }
+
+    if ti < TURN {
+        evaluate(&st, input)
+    } else {
+        st.compute_score() as f64
     }
 }
 
-impl State {
-    fn new() -> Self {
-        let board = mat![0; N; N];
+// 傾ける方向ごとのプレイアウト結果
+struct Arm {
+    dir: char,
+    st: State, // 傾けた後の盤面
+    n: usize,
+    sum: f64,
+    sum_sq: f64,
+}
+impl Arm {
+    fn new(dir: char, st: State) -> Self {
+        Self {
+            dir,
+            st,
+            n: 0,
+            sum: 0.0,
+            sum_sq: 0.0,
+        }
+    }
 
-        Self { board, t: 0 }
+    fn push(&mut self, value: f64) {
+        self.n += 1;
+        self.sum += value;
+        self.sum_sq += value * value;
     }
 
-    fn apply_put(&mut self, p: usize, input: &Input) {
-        // 入力お菓子の置き場所の検索
-        let mut cnt = 0;
-        for i in 0..N {
-            for j in 0..N {
-                if self.board[i][j] == 0 {
-                    cnt += 1;
-                    if cnt == p {
-                        self.board[i][j] = input.fs[self.t];
-                    }
-                }
-            }
+    fn mean(&self) -> f64 {
+        self.sum / self.n as f64
+    }
+
+    // 標本分散
+    fn var(&self) -> f64 {
+        (self.sum_sq / self.n as f64 - self.mean().powi(2)).max(0.0)
+    }
+}
+
+// 最善手の平均が他の全ての方向より十分に大きいか
+fn is_decided(arms: &[Arm], best: usize, z: f64) -> bool {
+    let b = &arms[best];
+    arms.iter().enumerate().all(|(i, a)| {
+        i == best || b.mean() - a.mean() > z * (b.var() / b.n as f64 + a.var() / a.n as f64).sqrt()
+    })
+}
+
+// UCB1 でプレイアウトを割り振り、平均が最も良い方向を返す
+// => (方向, プレイアウト数, 方向ごとの推定スコア)
+fn select_move_ucb(
+    st: &State,
+    ti: usize,
+    policy: &dyn Policy,
+    input: &Input,
+    tm: &TimeManager,
+    deadline: u128,
+    rng: &mut CoreRng,
+) -> (char, usize, Vec<(char, f64)>) {
+    // 同じ盤面になる方向はまとめる
+    let mut arms: Vec<Arm> = vec![];
+    for &dir in &DIRS {
+        let mut next_st = *st;
+        let _ = next_st.apply_move(dir);
+        if arms.iter().all(|a| a.st != next_st) {
+            arms.push(Arm::new(dir, next_st));
         }
     }
+    if arms.len() == 1 {
+        return (arms[0].dir, 0, vec![]);
+    }
 
-    fn apply_move(&mut self, dir: char) -> Result<(), String> {
-        match dir {
-            'L' => {
-                for i in 0..N {
-                    let mut k = 0;
-                    for j in 0..N {
-                        if self.board[i][j] != 0 {
-                            self.board[i][k] = self.board[i][j];
-                            if k != j {
-                                self.board[i][j] = 0;
-                            }
-                            k += 1;
-                        }
-                    }
-                }
-            }
-            'R' => {
-                for i in 0..N {
-                    let mut k = N - 1;
-                    for j in (0..N).rev() {
-                        if self.board[i][j] != 0 {
-                            self.board[i][k] = self.board[i][j];
-                            if k != j {
-                                self.board[i][j] = 0;
-                            }
-                            k -= 1;
-                        }
-                    }
-                }
-            }
-            'F' => {
-                for j in 0..N {
-                    let mut k = 0;
-                    for i in 0..N {
-                        if self.board[i][j] != 0 {
-                            self.board[k][j] = self.board[i][j];
-                            if k != i {
-                                self.board[i][j] = 0;
-                            }
-                            k += 1;
-                        }
-                    }
-                }
-            }
-            'B' => {
-                for j in 0..N {
-                    let mut k = N - 1;
-                    for i in (0..N).rev() {
-                        if self.board[i][j] != 0 {
-                            self.board[k][j] = self.board[i][j];
-                            if k != i {
-                                self.board[i][j] = 0;
-                            }
-                            k -= 1;
-                        }
-                    }
-                }
+    let den = input.score_den() as f64;
+    for arm in arms.iter_mut() {
+        for _ in 0..UCB_MIN_PLAYOUT {
+            arm.push(playout(ti + 1, arm.st, policy, input, rng) / den);
+        }
+    }
+
+    let mut total = arms.len() * UCB_MIN_PLAYOUT;
+    let mut best = 0;
+    for iter in 0.. {
+        if iter % UCB_CHECK_INTERVAL == 0 {
+            best = (0..arms.len())
+                .max_by(|&i, &j| arms[i].mean().partial_cmp(&arms[j].mean()).unwrap())
+                .unwrap();
+            if tm.elapsed() >= deadline || is_decided(&arms, best, input.search_params.ucb_stop_z) {
+                break;
             }
-            _ => {
-                return Err(format!("Illegal output: {}", dir));
+        }
+
+        let ln_total = (total as f64).ln();
+        let i = (0..arms.len())
+            .max_by(|&i, &j| {
+                let c = input.search_params.ucb_c;
+                let ucb = |a: &Arm| a.mean() + c * (ln_total / a.n as f64).sqrt();
+                ucb(&arms[i]).partial_cmp(&ucb(&arms[j])).unwrap()
+            })
+            .unwrap();
+        let value = playout(ti + 1, arms[i].st, policy, input, rng) / den;
+        arms[i].push(value);
+        total += 1;
+    }
+
+    // まとめた方向にも同じ値を付ける
+    let values = DIRS
+        .iter()
+        .filter_map(|&dir| {
+            let mut next_st = *st;
+            let _ = next_st.apply_move(dir);
+            arms.iter()
+                .find(|a| a.st == next_st)
+                .map(|a| (dir, 1e6 * a.mean()))
+        })
+        .collect();
+
+    (arms[best].dir, total, values)
+}
+
+// MCTS で読むときの局面。t 個置いた盤面なら p を引く番、t + 1 個なら傾ける番
+#[derive(Clone)]
+struct CandyGame<'a> {
+    st: State,
+    input: &'a Input,
+}
+
+impl<'a> Game for CandyGame<'a> {
+    type Action = char;
+    type Event = usize;
+
+    fn phase(&self) -> Phase {
+        if self.st.t == TURN {
+            Phase::Terminal
+        } else if self.st.occupied().count_ones() as usize > self.st.t {
+            Phase::Decision
+        } else {
+            Phase::Chance
+        }
+    }
+
+    // 同じ盤面になる方向はまとめる
+    fn actions(&self) -> Vec<char> {
+        let mut res: Vec<(char, State)> = vec![];
+        for &dir in &DIRS {
+            let mut next_st = self.st;
+            let _ = next_st.apply_move(dir);
+            if res.iter().all(|(_, s)| *s != next_st) {
+                res.push((dir, next_st));
             }
         }
 
-        self.t += 1;
+        res.into_iter().map(|(dir, _)| dir).collect()
+    }
+
+    fn apply(&mut self, dir: &char) {
+        let _ = self.st.apply_move(*dir);
+    }
+
+    fn sample_event(&self, rng: &mut CoreRng) -> usize {
+        rng.gen_range(1, TURN - self.st.t + 1)
+    }
+
+    fn apply_event(&mut self, p: &usize) {
+        self.st.apply_put(*p, &self.input.fs);
+    }
+
+    fn value(&self) -> f64 {
+        self.st.compute_score() as f64 / self.input.score_den() as f64
+    }
+}
+
+// 残りは Policy でプレイアウトする
+struct PolicyRollout<'a> {
+    policy: &'a dyn Policy,
+}
+impl<'a, 'b> Rollout<CandyGame<'b>> for PolicyRollout<'a> {
+    fn rollout(&self, game: &CandyGame<'b>, rng: &mut CoreRng) -> f64 {
+        let mut st = game.st;
+        if game.phase() == Phase::Decision {
+            let dir = self.policy.choose(st.t, &st, game.input, rng);
+            let _ = st.apply_move(dir);
+        }
+
+        playout(st.t, st, self.policy, game.input, rng) / game.input.score_den() as f64
+    }
+}
+
+// 置いた後の盤面から、p の引き方も木に含めて MCTS で読む
+// => (方向, 反復数, 方向ごとの推定スコア)
+fn select_move_mcts(
+    st: &State,
+    policy: &dyn Policy,
+    input: &Input,
+    tm: &TimeManager,
+    deadline: u128,
+    rng: &mut CoreRng,
+) -> (char, usize, Vec<(char, f64)>) {
+    let game = CandyGame { st: *st, input };
+    if game.actions().len() == 1 {
+        return (game.actions()[0], 0, vec![]);
+    }
+
+    let mcts = Mcts::new(MCTS_C, Budget::Timer(tm.timer.until(deadline)))
+        .max_depth(MCTS_DEPTH)
+        .expand_after(MCTS_EXPAND_AFTER);
+    let res = mcts.search(&game, &PolicyRollout { policy }, rng);
+
+    // まとめた方向にも同じ値を付ける
+    let values = DIRS
+        .iter()
+        .filter_map(|&dir| {
+            let mut next_st = *st;
+            let _ = next_st.apply_move(dir);
+            res.children
+                .iter()
+                .find(|(d, _, _)| {
+                    let mut s = *st;
+                    let _ = s.apply_move(*d);
+                    s == next_st
+                })
+                .map(|&(_, _, mean)| (dir, 1e6 * mean))
+        })
+        .collect();
+
+    (res.action, res.iterations, values)
+}
+
+// 探索が始まるまでの各ターンの読み方
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Search {
+    Ucb,  // 次の一手だけを UCB1 で比べる
+    Mcts, // p の引き方も含めて木を伸ばす
+}
+// ucb | mcts
+impl std::str::FromStr for Search {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "ucb" => Ok(Search::Ucb),
+            "mcts" => Ok(Search::Mcts),
+            _ => Err(format!("unknown search: {}", s)),
+        }
+    }
+}
+
+// 1ターンごとの記録 (replay で見る)
+// 1行目に f、以降ターンごとに "ターン p 方向 F:推定値 B:.. L:.. R:.." と傾けた後の盤面 N 行
+struct GameLog {
+    path: String,
+    out: std::io::BufWriter<std::fs::File>,
+}
+impl GameLog {
+    fn create(path: &str, input: &Input) -> std::io::Result<Self> {
+        let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let fs = input
+            .fs
+            .iter()
+            .map(|f| f.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(out, "{}", fs)?;
 
-        Ok(())
+        Ok(Self {
+            path: path.to_string(),
+            out,
+        })
     }
 
-    // 分子部分の算出
-    fn compute_score(&self) -> usize {
-        // スコア計算 分子部分。
-        let mut visited = mat![false; N; N];
-        let mut num = 0;
+    fn write_turn(
+        &mut self,
+        ti: usize,
+        p: usize,
+        dir: char,
+        values: &[(char, f64)],
+        st: &State,
+    ) -> std::io::Result<()> {
+        let values = DIRS
+            .iter()
+            .map(|&d| match values.iter().find(|(e, _)| *e == d) {
+                Some((_, v)) => format!("{}:{:.0}", d, v),
+                None => format!("{}:-", d),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(self.out, "{} {} {} {}", ti, p, dir, values)?;
         for i in 0..N {
-            for j in 0..N {
-                if !visited[i][j] && self.board[i][j] != 0 {
-                    visited[i][j] = true;
-                    let c = self.board[i][j];
-                    let mut size = 1;
-                    let mut stack = vec![(i, j)];
-                    while let Some((i, j)) = stack.pop() {
-                        for &(di, dj) in &DIJ {
-                            let i2 = i + di;
-                            let j2 = j + dj;
-                            if i2 < N && j2 < N && !visited[i2][j2] && self.board[i2][j2] == c {
-                                visited[i2][j2] = true;
-                                stack.push((i2, j2));
-                                size += 1;
-                            }
-                        }
-                    }
-                    num += size * size;
-                }
-            }
+            let row = (0..N).map(|j| st.get(i, j).to_string()).collect::<String>();
+            writeln!(self.out, "{}", row)?;
         }
 
-        num
+        // 途中で落ちてもそこまでの記録が残るよう、ターンごとに書き出す
+        self.out.flush()
     }
 }
 
-fn play(ti: usize, ok_flag: &mut bool, input: &Input) -> char {
-    if ti == TURN - 1 {
-        'R'
-    } else {
-        if input.fs[ti + 1] == input.candy1 {
-            *ok_flag = false;
-            'L'
-        } else {
-            if !(*ok_flag) {
-                *ok_flag = true;
-                'R'
-            }
-            // 上下動させる
-            else if input.fs[ti + 1] == input.candy2 {
-                'F'
-            } else {
-                'B'
-            }
+// ターン ti の打ち手を決める (置いた直後の盤面で呼ぶ)
+// => (方向, プレイアウト数, 方向ごとの推定スコア)
+fn decide(
+    st: &State,
+    ti: usize,
+    policy: &dyn Policy,
+    input: &Input,
+    tm: &TimeManager,
+    rng: &mut CoreRng,
+) -> (char, usize, Vec<(char, f64)>) {
+    let deadline = tm.deadline(ti);
+    if ti <= input.search_params.last_search_turn {
+        match input.search_params.search {
+            Search::Ucb => select_move_ucb(st, ti, policy, input, tm, deadline, rng),
+            Search::Mcts => select_move_mcts(st, policy, input, tm, deadline, rng),
         }
     }
+    // なに返しても一緒
+    else if ti == TURN - 1 {
+        ('R', 0, vec![]) // dummy
+    }
+    // 探索
+    else {
+        let depth = input.search_params.expectimax_depth;
+        let (dir, values) = simulate(st, input, depth, tm, deadline);
+        (dir, 0, values)
+    }
 }
 
-// プレイアウトしたスコアを返す
-// ti: turn - 1
-fn playout(mut ti: usize, mut st: State, play_list: &Vec<char>, input: &Input) -> usize {
-    let mut rng = thread_rng();
+// p を全部知っているときの探索ノード
+struct OfflineNode<'a> {
+    st: State,
+    input: &'a Input,
+    ps: &'a [usize],
+}
 
-    while ti < TURN {
-        let point = rng.gen_range(1, TURN - ti + 1);
+impl<'a> Beam for OfflineNode<'a> {
+    type Action = char;
+    type Key = State;
 
-        st.apply_put(point, input);
+    fn successors(&self) -> Vec<(char, Self)> {
+        if self.st.t == TURN {
+            return vec![];
+        }
+        let mut st = self.st;
+        st.apply_put(self.ps[st.t], &self.input.fs);
 
-        let c = play_list[ti];
+        DIRS.iter()
+            .map(|&dir| {
+                let mut next_st = st;
+                let _ = next_st.apply_move(dir);
+                (
+                    dir,
+                    OfflineNode {
+                        st: next_st,
+                        input: self.input,
+                        ps: self.ps,
+                    },
+                )
+            })
+            .collect()
+    }
 
-        let _ = st.apply_move(c);
+    fn evaluate(&self) -> f64 {
+        if self.st.t == TURN {
+            self.st.compute_score() as f64
+        } else {
+            evaluate(&self.st, self.input)
+        }
+    }
 
-        ti += 1;
+    fn key(&self) -> State {
+        self.st
+    }
+}
+
+// p を全部知っているとしてビームサーチで傾け方を決める (オンラインで取れるスコアのほぼ上限)
+// => (分子部分, 傾け方)
+fn solve_offline(fs: &[usize], ps: &[usize], beam_width: usize) -> (usize, Vec<char>) {
+    let input = Input::new(fs.to_vec());
+    let res = BeamSearch::new(beam_width, TURN).run(OfflineNode {
+        st: State::new(),
+        input: &input,
+        ps,
+    });
+
+    (res.state.st.compute_score(), res.actions)
+}
+
+// 1手ずつ p を明かしながら本番と同じように遊ぶ => 分子部分
+// timer を偽の時計にして rng のシードを決めれば、何度やっても同じ結果になる
+fn play_online(
+    fs: &[usize],
+    ps: &[usize],
+    params: &Params,
+    timer: Timer,
+    rng: &mut CoreRng,
+) -> usize {
+    let mut input = Input::new(fs.to_vec());
+    input.search_params = SearchParams::new(params);
+    input.eval_weights = input.eval_weights.with_params(params);
+    let policy = policy_from_params(params, &input);
+    let tm = TimeManager::new(timer, input.search_params.midgame_weight);
+
+    let mut st = State::new();
+    for (ti, &p) in ps.iter().enumerate() {
+        input.ps.push(p);
+        st.apply_put(p, &input.fs);
+        let (dir, _, _) = decide(&st, ti, policy.as_ref(), &input, &tm, rng);
+        st.apply_move(dir).unwrap();
     }
 
     st.compute_score()
 }
 
+// 未来を知っている場合のスコアと、オンラインで遊んだときとの差 (regret) を表示する
+fn offline(cases: &[(String, Vec<usize>, Vec<usize>)]) {
+    let params = Params::from_env();
+    let mut bound_sum = 0;
+    let mut online_sum = 0;
+    for (name, fs, ps) in cases {
+        let den = Input::new(fs.clone()).score_den() as f64;
+        let (num, _) = solve_offline(fs, ps, OFFLINE_BEAM_WIDTH);
+        let bound = (1e6 * num as f64 / den).round() as i64;
+        let timer = Timer::new(TIME_LIMIT).margin(TIME_MARGIN);
+        let num = play_online(fs, ps, &params, timer, &mut CoreRng::from_time());
+        let online = (1e6 * num as f64 / den).round() as i64;
+        println!(
+            "{:<16} bound: {:>8}  online: {:>8}  regret: {:>8}",
+            name,
+            bound,
+            online,
+            bound - online
+        );
+        bound_sum += bound;
+        online_sum += online;
+    }
+
+    let case_num = cases.len().max(1) as i64;
+    println!(
+        "{:<16} bound: {:>8}  online: {:>8}  regret: {:>8}",
+        "average",
+        bound_sum / case_num,
+        online_sum / case_num,
+        (bound_sum - online_sum) / case_num
+    );
+}
+
+// judge を使わずに同じ seed を2つのパラメータで遊び、seed ごとの差と対応のある t 検定を表示する
+// (heuristic-core の compare と同じ表。B が良くなった向きを正にする)
+fn compare(seed_from: u64, seed_to: u64, params_a: &Params, params_b: &Params) {
+    let mut a = vec![];
+    let mut b = vec![];
+    println!(
+        "{:>6}  {:>10}  {:>10}  {:>10}  {:>8}",
+        "seed", "A", "B", "delta", "rel%"
+    );
+    for seed in seed_from..=seed_to {
+        let (fs, ps) = gen_input(seed);
+        let den = Input::new(fs.clone()).score_den() as f64;
+        // 壁時計だと負荷で結果が揺れるので、偽の時計と seed で固定した乱数で遊ぶ
+        let score = |params: &Params| {
+            let timer = Timer::fake(TIME_LIMIT, COMPARE_CLOCK_STEP).margin(TIME_MARGIN);
+            let num = play_online(&fs, &ps, params, timer, &mut CoreRng::new(seed));
+            (1e6 * num as f64 / den).round()
+        };
+        let (sa, sb) = (score(params_a), score(params_b));
+        println!(
+            "{:>6}  {:>10}  {:>10}  {:>+10}  {:>+8.3}",
+            seed,
+            sa,
+            sb,
+            sb - sa,
+            100.0 * (sb - sa) / sa.max(1.0)
+        );
+        a.push(sa);
+        b.push(sb);
+    }
+
+    println!("{}", Paired::new(&a, &b, false));
+}
+
+// 方策の比較: 同じ seed の対局を方策だけで打ち切ったスコアと、プレイアウトの速さを出す
+// Usage: ahc015 --bench <seed_from> <seed_to> [policy...]
+fn bench(seed_from: u64, seed_to: u64, policies: &[String]) {
+    for name in policies {
+        let mut score_sum = 0;
+        let mut playout_num = 0;
+        let start = Instant::now();
+        for seed in seed_from..=seed_to {
+            let (fs, ps) = gen_input(seed);
+            let input = Input::new(fs);
+            let policy = match parse_policy(name, &input) {
+                Some(policy) => policy,
+                None => {
+                    eprintln!("unknown policy: {}", name);
+                    std::process::exit(1)
+                }
+            };
+
+            let mut st = State::new();
+            let mut rng = CoreRng::new(seed);
+            for (ti, &p) in ps.iter().enumerate() {
+                st.apply_put(p, &input.fs);
+                let dir = policy.choose(ti, &st, &input, &mut rng);
+                let _ = st.apply_move(dir);
+            }
+            score_sum +=
+                (1e6 * st.compute_score() as f64 / input.score_den() as f64).round() as usize;
+
+            for _ in 0..BENCH_PLAYOUT_NUM {
+                playout(0, State::new(), policy.as_ref(), &input, &mut rng);
+            }
+            playout_num += BENCH_PLAYOUT_NUM;
+        }
+
+        let seed_num = (seed_to - seed_from + 1) as usize;
+        let ms = start.elapsed().as_millis().max(1);
+        println!(
+            "{:<16} score: {:>8}  playouts: {:>8.0}/s",
+            name,
+            score_sum / seed_num,
+            playout_num as f64 * 1000.0 / ms as f64
+        );
+    }
+}
+
+fn usage() -> ! {
+    let name = std::env::args().next().unwrap();
+    eprintln!("Usage: {} < input", name);
+    eprintln!("       {} --bench <seed_from> <seed_to> [policy...]", name);
+    eprintln!(
+        "       {} --compare <seed_from> <seed_to> <params_a> <params_b>",
+        name
+    );
+    eprintln!(
+        "       {} --offline <seed_from> <seed_to> | --offline --input FILE...",
+        name
+    );
+    std::process::exit(1)
+}
+
+fn parse_seed(s: &str) -> u64 {
+    s.parse().unwrap_or_else(|_| usage())
+}
+
 fn main() {
-    let system_time = SystemTime::now();
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.len() >= 4 && args[1] == "--bench" {
+        let policies = if args.len() > 4 {
+            args[4..].to_vec()
+        } else {
+            ["scripted", "random", "greedy", "mixed:0.1"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect()
+        };
+        bench(parse_seed(&args[2]), parse_seed(&args[3]), &policies);
+        return;
+    }
+    // ahc015 --compare <seed_from> <seed_to> <params_a> <params_b>  (例: "" "ucb_c=0.1")
+    if args.len() == 6 && args[1] == "--compare" {
+        let (params_a, params_b) = (Params::parse(&args[4]), Params::parse(&args[5]));
+        for name in params_a.unknown(&PARAM_NAMES) {
+            eprintln!("unknown param: {}", name);
+        }
+        for name in params_b.unknown(&PARAM_NAMES) {
+            eprintln!("unknown param: {}", name);
+        }
+        compare(
+            parse_seed(&args[2]),
+            parse_seed(&args[3]),
+            &params_a,
+            &params_b,
+        );
+        return;
+    }
+    // ahc015 --offline <seed_from> <seed_to>  または  ahc015 --offline --input FILE...
+    if args.len() >= 4 && args[1] == "--offline" {
+        let cases = if args[2] == "--input" {
+            args[3..]
+                .iter()
+                .map(|path| {
+                    let (fs, ps) = std::fs::read_to_string(path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|s| parse_input(&s))
+                        .unwrap_or_else(|e| {
+                            eprintln!("invalid input {}: {}", path, e);
+                            std::process::exit(1)
+                        });
+                    (path.clone(), fs, ps)
+                })
+                .collect::<Vec<_>>()
+        } else {
+            let seed_from = parse_seed(&args[2]);
+            let seed_to = parse_seed(&args[3]);
+            (seed_from..=seed_to)
+                .map(|seed| {
+                    let (fs, ps) = gen_input(seed);
+                    (format!("seed {}", seed), fs, ps)
+                })
+                .collect()
+        };
+        offline(&cases);
+        return;
+    }
+    // 引数の足りないモード指定などで標準入力を待ち続けないように
+    if args.len() > 1 {
+        usage();
+    }
+
+    let timer = Timer::new(TIME_LIMIT).margin(TIME_MARGIN);
+    let params = Params::from_env();
+    for name in params.unknown(&PARAM_NAMES) {
+        eprintln!("unknown param: {}", name);
+    }
+    let search_params = SearchParams::new(&params);
+    let tm = TimeManager::new(timer, search_params.midgame_weight);
 
     // tool
     let (r, w) = (std::io::stdin(), std::io::stdout());
@@ -307,93 +1264,240 @@ fn main() {
     // input
     let f = (0..TURN).map(|_| sc.read::<usize>()).collect::<Vec<_>>();
     let mut input = Input::new(f);
+    input.eval_weights = input.eval_weights.with_params(&params);
+    input.search_params = search_params;
 
     let mut st = State::new();
+    let mut rng = CoreRng::from_time();
 
     /* action */
-    let mut play_list = vec![];
-    let mut ok_flag = true; // 上下動しても良し
-    for i in 0..TURN {
-        let c = play(i, &mut ok_flag, &input);
-        play_list.push(c);
-    }
+    let policy = policy_from_params(&params, &input);
+
+    // AHC015_LOG=path でターンごとの記録を書き出す
+    // 書けないときは黙って続けず止める
+    let mut log = std::env::var("AHC015_LOG").ok().map(|path| {
+        GameLog::create(&path, &input).unwrap_or_else(|e| {
+            eprintln!("cannot write {}: {}", path, e);
+            std::process::exit(1)
+        })
+    });
 
+    let mut playout_num = 0;
     for ti in 0..TURN {
         let p: usize = sc.read();
         input.ps.push(p);
 
-        st.apply_put(p, &input);
+        st.apply_put(p, &input.fs);
 
-        let ans = if ti <= LAST_SEARCH_TURN {
-            let mut best = 0.0;
-            let mut best_ans = '0';
-            for &dir in &DIRS {
-                let mut tmp_st = st.clone();
-                let _ = tmp_st.apply_move(dir);
+        let (ans, num, values) = decide(&st, ti, policy.as_ref(), &input, &tm, &mut rng);
+        playout_num += num;
 
-                let mut sum = 0.0;
-                for _ in 0..SINGLE_PLAYOUT_NUM {
-                    let score = playout(ti + 1, tmp_st.clone(), &play_list, &input) as f64;
-                    sum += score;
-                }
-
-                let avg = sum / SINGLE_PLAYOUT_NUM as f64;
-                if avg > best {
-                    best = avg;
-                    best_ans = dir;
-                }
+        // 盤面がジャッジとずれないよう、出せない手なら出す前に止める
+        st.apply_move(ans).unwrap();
+        if let Some(log) = log.as_mut() {
+            if let Err(e) = log.write_turn(ti, p, ans, &values, &st) {
+                eprintln!("cannot write {}: {}", log.path, e);
+                std::process::exit(1);
             }
-
-            best_ans
-        }
-        // なに返しても一緒
-        else if ti == TURN - 1 {
-            'R' // dummy
         }
-        // 探索
-        else {
-            simulate(&st, &input)
-        };
-
-        let _ = st.apply_move(ans);
 
         println!("{}", ans);
     }
 
     // eprintln!("{}", st.compute_score());
+    eprintln!("{} playouts", playout_num);
 
-    eprintln!("{}ms", system_time.elapsed().unwrap().as_millis());
+    eprintln!("{}ms", tm.elapsed());
 }
 
-pub struct IO<R, W: std::io::Write>(R, std::io::BufWriter<W>);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // depth 回置くまで全探索した期待値 (読み切れなければ evaluate)
+    fn brute_force(st: State, input: &Input, depth: usize) -> f64 {
+        if st.t == TURN {
+            return st.compute_score() as f64;
+        }
+        if depth == 0 {
+            return evaluate(&st, input);
+        }
+        let empty = TURN - st.t;
+        let mut acc = 0.0;
+        for p in 1..=empty {
+            let mut put_st = st;
+            put_st.apply_put(p, &input.fs);
+            acc += DIRS
+                .iter()
+                .map(|&dir| {
+                    let mut next_st = put_st;
+                    next_st.apply_move(dir).unwrap();
+                    brute_force(next_st, input, depth - 1)
+                })
+                .fold(f64::NEG_INFINITY, f64::max);
+        }
 
-impl<R: std::io::Read, W: std::io::Write> IO<R, W> {
-    pub fn new(r: R, w: W) -> IO<R, W> {
-        IO(r, std::io::BufWriter::new(w))
+        acc / empty as f64
     }
-    pub fn write<S: ToString>(&mut self, s: S) {
-        use std::io::Write;
-        self.1.write(s.to_string().as_bytes()).unwrap();
+
+    // 残りマスが少ない盤面を乱択で作る
+    fn make_tiny_board(seed: u64, rest: usize) -> (State, Input) {
+        let mut rng = rand_pcg::Pcg64Mcg::new(seed as u128);
+        let fs = (0..TURN)
+            .map(|_| rng.gen_range(1, CANDY_KIND + 1))
+            .collect();
+        let input = Input::new(fs);
+        let mut st = State::new();
+        while st.t < TURN - rest {
+            st.apply_put(rng.gen_range(1, TURN - st.t + 1), &input.fs);
+            st.apply_move(DIRS[rng.gen_range(0, 4)]).unwrap();
+        }
+
+        (st, input)
     }
-    pub fn read<T: std::str::FromStr>(&mut self) -> T {
-        use std::io::Read;
-        let buf = self
-            .0
-            .by_ref()
-            .bytes()
-            .map(|b| b.unwrap())
-            .skip_while(|&b| b == b' ' || b == b'\n' || b == b'\r' || b == b'\t')
-            .take_while(|&b| b != b' ' && b != b'\n' && b != b'\r' && b != b'\t')
-            .collect::<Vec<_>>();
-        unsafe { std::str::from_utf8_unchecked(&buf) }
-            .parse()
-            .ok()
-            .expect("Parse error.")
+
+    #[test]
+    fn expectimax_matches_brute_force() {
+        for seed in 0..10 {
+            for rest in 1..=4 {
+                let (st, input) = make_tiny_board(seed, rest);
+                let expected = brute_force(st, &input, rest);
+                let actual = simulate_put(st, &input, rest);
+                assert!((expected - actual).abs() < 1e-9, "{} {}", expected, actual);
+            }
+        }
     }
-    pub fn vec<T: std::str::FromStr>(&mut self, n: usize) -> Vec<T> {
-        (0..n).map(|_| self.read()).collect()
+
+    #[test]
+    fn expectimax_matches_brute_force_when_cut_off() {
+        // 残りより浅く読むので葉は evaluate。負になる重みでも同じになる
+        for seed in 0..5 {
+            for &(rest, depth) in &[(4, 1), (5, 2), (6, 2)] {
+                for &weights in &[EVAL_WEIGHTS, NEGATIVE_WEIGHTS] {
+                    let (st, mut input) = make_tiny_board(seed, rest);
+                    input.eval_weights = weights;
+                    let expected = brute_force(st, &input, depth);
+                    let actual = simulate_put(st, &input, depth);
+                    assert!(
+                        (expected - actual).abs() < 1e-6 * expected.abs().max(1.0),
+                        "{} {}",
+                        expected,
+                        actual
+                    );
+                }
+            }
+        }
+    }
+
+    // 距離と境界だけを引く重み。途中の盤面の評価は全て負になる
+    const NEGATIVE_WEIGHTS: EvalWeights = EvalWeights {
+        score: 0.0,
+        adjacency: 0.0,
+        distance: 1.0,
+        boundary: 1.0,
+        merge: 0.0,
+    };
+
+    #[test]
+    fn expectimax_handles_negative_leaves() {
+        let (mut st, mut input) = make_tiny_board(3, 30);
+        input.eval_weights = NEGATIVE_WEIGHTS;
+        st.apply_put(1, &input.fs);
+
+        let value = simulate_move(st, &input, 1);
+        assert!(value < 0.0, "{}", value);
+        let (dir, values) = simulate_depth(&st, &input, 1);
+        assert!(values.iter().all(|&(_, v)| v < 0.0), "{:?}", values);
+        let mut next_st = st;
+        assert!(next_st.apply_move(dir).is_ok(), "{}", dir);
+        let best = values
+            .iter()
+            .map(|&(_, v)| v)
+            .fold(f64::NEG_INFINITY, f64::max);
+        assert_eq!(values.iter().find(|&&(d, _)| d == dir).unwrap().1, best);
     }
-    pub fn chars(&mut self) -> Vec<char> {
-        self.read::<String>().chars().collect()
+
+    #[test]
+    fn expectimax_uses_evaluation_at_depth_limit() {
+        let (st, input) = make_tiny_board(0, 5);
+        assert_eq!(simulate_put(st, &input, 0), evaluate(&st, &input));
+    }
+
+    #[test]
+    fn simulate_picks_a_tilt() {
+        let (mut st, input) = make_tiny_board(1, 3);
+        st.apply_put(1, &input.fs);
+        let tm = TimeManager::new(Timer::fake(TIME_LIMIT, 1.0), MIDGAME_WEIGHT);
+        let (dir, values) = simulate(&st, &input, EXPECTIMAX_DEPTH, &tm, u128::MAX);
+        assert!(DIRS.contains(&dir));
+        assert_eq!(values.len(), DIRS.len());
+    }
+
+    #[test]
+    fn simulate_stops_at_depth_one_after_overrunning() {
+        // 時計を読むたびに 1ms 進むので深さ1に 1ms かかったことになり、締め切りまで 1ms しかなければ深くしない
+        let (mut st, input) = make_tiny_board(2, 30);
+        st.apply_put(1, &input.fs);
+        let tm = TimeManager::new(Timer::fake(TIME_LIMIT, 1.0), MIDGAME_WEIGHT);
+        let start = tm.elapsed();
+        let res = simulate(&st, &input, EXPECTIMAX_DEPTH, &tm, start + 1);
+        assert_eq!(res, simulate_depth(&st, &input, 1));
+    }
+
+    #[test]
+    fn offline_bound_is_not_below_online_score() {
+        // ビームを狭くし、偽の時計を速く進めて探索を軽くする
+        let (fs, ps) = gen_input(0);
+        let (bound, actions) = solve_offline(&fs, &ps, 100);
+        assert_eq!(actions.len(), TURN);
+        let timer = Timer::fake(TIME_LIMIT, 10.0);
+        let online = play_online(&fs, &ps, &Params::default(), timer, &mut CoreRng::new(0));
+        assert!(bound >= online, "{} {}", bound, online);
+    }
+
+    #[test]
+    fn parse_policy_rejects_unknown_names() {
+        let input = Input::new(vec![1; TURN]);
+        for s in &[
+            "scripted",
+            "random",
+            "greedy",
+            "mixed:0.1",
+            "mixed:0.1:greedy",
+        ] {
+            assert!(parse_policy(s, &input).is_some(), "{}", s);
+        }
+        for s in &[
+            "",
+            "bogus",
+            "mixed",
+            "mixed:2",
+            "mixed:x",
+            "mixed:0.1:bogus",
+        ] {
+            assert!(parse_policy(s, &input).is_none(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn time_manager_spends_the_limit_over_all_turns() {
+        // 時計を読むたびに 1ms 進む。各ターン締め切りまで使い切る
+        let tm = TimeManager::new(
+            Timer::fake(TIME_LIMIT, 1.0).margin(TIME_MARGIN),
+            MIDGAME_WEIGHT,
+        );
+        let mut spent = vec![];
+        for ti in 0..TURN {
+            let start = tm.elapsed();
+            let deadline = tm.deadline(ti);
+            while tm.elapsed() < deadline {}
+            spent.push(tm.elapsed() - start);
+        }
+
+        let end = tm.elapsed();
+        assert!(end <= TIME_LIMIT, "{}", end);
+        assert!(end >= TIME_LIMIT - TIME_MARGIN, "{}", end);
+        // 中盤に多めに割り振る
+        assert!(spent[TURN / 2] > spent[1], "{:?}", spent);
     }
 }
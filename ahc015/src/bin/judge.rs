@@ -0,0 +1,155 @@
+// AHC015 のローカル対話ジャッジ
+// Usage: judge (--seed SEED | --input FILE) [--time-limit MS] <solver> [args...]
+//   ソルバーを起動して f と各ターンの p を渡し、傾ける方向を受け取ってゲームを進める。
+//   入力ファイルは公式と同じ形式 (1行目に f_1..f_100, 続いて p_1..p_100)
+//   起動から最後の出力までが MS (default: 本番と同じ 2000) を超えたら止めて TLE にする
+
+use ahc015::game::{gen_input, parse_input, score, State, DIRS, TURN};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+const TIME_LIMIT: u64 = 2_000; // ms
+
+struct Input {
+    fs: Vec<usize>,
+    ps: Vec<usize>,
+}
+
+fn read_input(path: &str) -> Input {
+    let s = std::fs::read_to_string(path).unwrap_or_else(|_| {
+        eprintln!("no such file: {}", path);
+        std::process::exit(1)
+    });
+    let (fs, ps) = parse_input(&s).unwrap_or_else(|e| {
+        eprintln!("invalid input {}: {}", path, e);
+        std::process::exit(1)
+    });
+
+    Input { fs, ps }
+}
+
+// ソルバーと対話してゲームを最後まで進める。
+// 時間切れや不正な出力のときは、ソルバーを止めてから返す
+fn play(input: &Input, solver: &[String], time_limit: Duration) -> Result<State, String> {
+    let start = Instant::now();
+    let mut child = Command::new(&solver[0])
+        .args(&solver[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to execute {}: {}", solver[0], e))?;
+    let mut stdin = child.stdin.take().unwrap();
+
+    // 読み込みで止まったままにならないよう、出力は別スレッドで読んで時間を区切って受け取る
+    let (tx, rx) = mpsc::channel();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    std::thread::spawn(move || {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match stdout.read_line(&mut line) {
+                Ok(0) => break,
+                Ok(_) if line.trim().is_empty() => continue,
+                Ok(_) => {
+                    if tx.send(Ok(line.trim().to_string())).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(e.to_string()));
+                    break;
+                }
+            }
+        }
+    });
+
+    let res = interact(input, &mut stdin, &rx, start + time_limit);
+    if res.is_err() {
+        let _ = child.kill();
+    }
+    let _ = child.wait();
+
+    res
+}
+
+fn interact(
+    input: &Input,
+    stdin: &mut ChildStdin,
+    rx: &Receiver<Result<String, String>>,
+    deadline: Instant,
+) -> Result<State, String> {
+    let fs = input
+        .fs
+        .iter()
+        .map(|f| f.to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    writeln!(stdin, "{}", fs).map_err(|e| e.to_string())?;
+    stdin.flush().map_err(|e| e.to_string())?;
+
+    let mut st = State::new();
+    for t in 0..TURN {
+        let p = input.ps[t];
+        st.apply_put(p, &input.fs);
+        writeln!(stdin, "{}", p).map_err(|e| e.to_string())?;
+        stdin.flush().map_err(|e| e.to_string())?;
+
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let ans = match rx.recv_timeout(timeout) {
+            Ok(line) => line?,
+            Err(RecvTimeoutError::Timeout) => return Err(format!("TLE at turn {}", t)),
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(format!("unexpected EOF at turn {}", t))
+            }
+        };
+        let dir = match ans.chars().next() {
+            Some(c) if ans.len() == 1 && DIRS.contains(&c) => c,
+            _ => return Err(format!("Illegal output at turn {}: {}", t, ans)),
+        };
+        st.apply_move(dir)?;
+    }
+
+    Ok(st)
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: {} (--seed SEED | --input FILE) [--time-limit MS] <solver> [args...]",
+        std::env::args().next().unwrap()
+    );
+    std::process::exit(1)
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.len() < 3 {
+        usage();
+    }
+    let input = match args[0].as_str() {
+        "--seed" => {
+            let (fs, ps) = gen_input(args[1].parse().unwrap_or_else(|_| usage()));
+            Input { fs, ps }
+        }
+        "--input" => read_input(&args[1]),
+        _ => usage(),
+    };
+    let (time_limit, solver) = if args[2] == "--time-limit" {
+        if args.len() < 5 {
+            usage();
+        }
+        (args[3].parse().unwrap_or_else(|_| usage()), &args[4..])
+    } else {
+        (TIME_LIMIT, &args[2..])
+    };
+
+    match play(&input, solver, Duration::from_millis(time_limit)) {
+        Ok(st) => eprintln!("Score = {}", score(&input.fs, &st)),
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!("Score = 0");
+            std::process::exit(1)
+        }
+    }
+}
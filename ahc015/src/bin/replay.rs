@@ -0,0 +1,236 @@
+// AHC015 のゲームログを HTML のアニメーションにする
+// Usage: replay <log> [out.html] [fps]
+//   log は ahc015 (src/bin/a.rs) を AHC015_LOG=path 付きで動かして書き出したもの。
+//   1ターン1フレームで、盤面と各方向の推定スコア、その時点のスコアを表示する
+
+use ahc015::game::{score_den, State, CANDY_KIND, N, TURN};
+use heuristic_core::movie::{Graphics, Movie};
+use std::fs;
+use std::io::Write;
+
+const CELL: f64 = 40.0;
+const TEXT_SIZE: f64 = 16.0;
+// キャンディの種類ごとの色
+const COLORS: [(f64, f64, f64); CANDY_KIND + 1] = [
+    (1.0, 1.0, 1.0),
+    (0.95, 0.4, 0.4),
+    (0.4, 0.8, 0.4),
+    (0.4, 0.55, 1.0),
+];
+
+// ログの1ターン分
+struct Turn {
+    t: usize,
+    p: usize,
+    dir: char,
+    // 方向ごとの推定スコア (F, B, L, R の順。無ければ "-")
+    values: Vec<String>,
+    board: State, // 傾けた後の盤面
+}
+
+// キャンディの種類は 1..=CANDY_KIND (盤面の 0 は空き)。範囲外は色もスコアも決まらないのでエラーにする
+fn parse_candy(c: char, empty_ok: bool) -> Option<usize> {
+    let d = c.to_digit(10)? as usize;
+    if d <= CANDY_KIND && (empty_ok || d != 0) {
+        Some(d)
+    } else {
+        None
+    }
+}
+
+fn parse_log(s: &str) -> Result<(Vec<usize>, Vec<Turn>), String> {
+    let mut lines = s.lines();
+    let head = lines.next().ok_or("empty log")?;
+    let fs = head
+        .split_whitespace()
+        .map(|e| {
+            let mut cs = e.chars();
+            match (cs.next().and_then(|c| parse_candy(c, false)), cs.next()) {
+                (Some(f), None) => Ok(f),
+                _ => Err(format!("invalid candy: {}", e)),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if fs.len() != TURN {
+        return Err(format!("expected {} candies, got {}", TURN, fs.len()));
+    }
+
+    let mut turns = vec![];
+    while let Some(head) = lines.next() {
+        let v = head.split_whitespace().collect::<Vec<_>>();
+        if v.len() < 3 {
+            return Err(format!("invalid turn line: {}", head));
+        }
+        let t = v[0]
+            .parse()
+            .ok()
+            .filter(|&t| t < TURN)
+            .ok_or(format!("invalid turn: {}", head))?;
+        let p = v[1].parse().map_err(|_| format!("invalid p: {}", head))?;
+        let dir = v[2].chars().next().unwrap();
+        let values = v[3..].iter().map(|e| e.to_string()).collect();
+
+        let mut board = State::new();
+        board.t = t + 1;
+        for i in 0..N {
+            let row = lines.next().ok_or("unexpected end of log")?;
+            let cells = row
+                .chars()
+                .map(|c| parse_candy(c, true))
+                .collect::<Option<Vec<_>>>()
+                .filter(|cells| cells.len() == N)
+                .ok_or(format!("invalid board row: {}", row))?;
+            for (j, &c) in cells.iter().enumerate() {
+                if c != 0 {
+                    board.masks[c - 1] |= 1 << (i * N + j);
+                }
+            }
+        }
+
+        turns.push(Turn {
+            t,
+            p,
+            dir,
+            values,
+            board,
+        });
+    }
+
+    Ok((fs, turns))
+}
+
+fn draw(g: &mut Graphics, fs: &[usize], turn: &Turn, den: usize) {
+    g.clear();
+
+    // 盤面
+    g.stroke_rgb(0.6, 0.6, 0.6);
+    for i in 0..N {
+        for j in 0..N {
+            let (r, gr, b) = COLORS[turn.board.get(i, j)];
+            g.fill_rgb(r, gr, b);
+            g.rect(j as f64 * CELL, i as f64 * CELL, CELL, CELL);
+        }
+    }
+
+    // 情報
+    g.no_stroke();
+    g.fill_rgb(0.0, 0.0, 0.0);
+    let w = N as f64 * CELL;
+    let mut y = w + TEXT_SIZE * 1.5;
+    let score = (1e6 * turn.board.compute_score() as f64 / den as f64).round();
+    g.text(
+        format!(
+            "turn {}: put {} at {}, tilt {}",
+            turn.t + 1,
+            fs[turn.t],
+            turn.p,
+            turn.dir
+        ),
+        w / 2.0,
+        y,
+        TEXT_SIZE,
+    );
+    y += TEXT_SIZE * 1.5;
+    g.text(format!("score {}", score), w / 2.0, y, TEXT_SIZE);
+    y += TEXT_SIZE * 1.5;
+    g.text(turn.values.join(" "), w / 2.0, y, TEXT_SIZE);
+
+    // 次のキャンディ
+    if turn.t + 1 < TURN {
+        y += TEXT_SIZE;
+        let (r, gr, b) = COLORS[fs[turn.t + 1]];
+        g.stroke_rgb(0.6, 0.6, 0.6);
+        g.fill_rgb(r, gr, b);
+        g.rect(w / 2.0 - CELL / 4.0, y, CELL / 2.0, CELL / 2.0);
+    }
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: {} <log> [out.html] [fps]",
+        std::env::args().next().unwrap()
+    );
+    std::process::exit(1)
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.is_empty() {
+        usage();
+    }
+    let out_path = args.get(1).map(|s| s.as_str()).unwrap_or("replay.html");
+    let fps = match args.get(2) {
+        Some(s) => s.parse().unwrap_or_else(|_| usage()),
+        None => 5,
+    };
+
+    let s = fs::read_to_string(&args[0]).unwrap_or_else(|_| {
+        eprintln!("no such file: {}", args[0]);
+        std::process::exit(1)
+    });
+    let (fs, turns) = parse_log(&s).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    });
+
+    let den = score_den(&fs);
+
+    let mut mov = Movie::new();
+    let mut g = Graphics::new();
+    g.screen(
+        N as f64 * CELL,
+        N as f64 * CELL + TEXT_SIZE * 6.0 + CELL / 2.0,
+    );
+    for turn in &turns {
+        draw(&mut g, &fs, turn, den);
+        mov.add_frame(&g);
+    }
+
+    let mut f = fs::File::create(out_path).unwrap();
+    f.write_all(mov.dump_html(fps).as_bytes()).unwrap();
+
+    if let Some(last) = turns.last() {
+        eprintln!(
+            "Score = {}",
+            (1e6 * last.board.compute_score() as f64 / den as f64).round()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(f: &str, row: &str) -> String {
+        let mut s = vec![f; TURN].join(" ");
+        s += "\n0 1 L F:- B:- L:1 R:2\n";
+        s += &format!("{}\n", row);
+        for _ in 1..N {
+            s += "0000000000\n";
+        }
+        s
+    }
+
+    #[test]
+    fn parse_log_reads_the_board() {
+        let (fs, turns) = parse_log(&log("2", "2300000001")).unwrap();
+        assert_eq!(fs, vec![2; TURN]);
+        assert_eq!(turns.len(), 1);
+        let board = &turns[0].board;
+        assert_eq!(
+            (board.get(0, 0), board.get(0, 1), board.get(0, 9)),
+            (2, 3, 1)
+        );
+        assert_eq!(board.compute_score(), 3);
+        assert_eq!(turns[0].values, vec!["F:-", "B:-", "L:1", "R:2"]);
+    }
+
+    #[test]
+    fn parse_log_rejects_unknown_candies() {
+        assert!(parse_log(&log("4", "1000000000")).is_err());
+        assert!(parse_log(&log("0", "1000000000")).is_err());
+        assert!(parse_log(&log("12", "1000000000")).is_err());
+        assert!(parse_log(&log("1", "4000000000")).is_err());
+        assert!(parse_log(&log("1", "100000000")).is_err());
+    }
+}
@@ -0,0 +1,255 @@
+// ゲームのルール: 盤面、置き方、傾け方、スコア
+// 盤面のビット位置は i * N + j
+
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+
+pub const TURN: usize = 100;
+pub const N: usize = 10;
+pub const CANDY_KIND: usize = 3;
+pub const DIRS: [char; 4] = ['F', 'B', 'L', 'R'];
+
+pub const FULL: u128 = (1 << (N * N)) - 1;
+pub const ROW_FIRST: u128 = (1 << N) - 1;
+pub const ROW_LAST: u128 = ROW_FIRST << (N * (N - 1));
+pub const COL_FIRST: u128 = {
+    let mut mask = 0;
+    let mut i = 0;
+    while i < N {
+        mask |= 1 << (i * N);
+        i += 1;
+    }
+    mask
+};
+pub const COL_LAST: u128 = COL_FIRST << (N - 1);
+
+// キャンディの種類ごとに盤面をビットで持つ
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct State {
+    pub masks: [u128; CANDY_KIND], // masks[c - 1]: 種類 c のキャンディがあるマス
+    pub t: usize,
+}
+
+impl State {
+    pub fn new() -> Self {
+        Self {
+            masks: [0; CANDY_KIND],
+            t: 0,
+        }
+    }
+
+    pub fn occupied(&self) -> u128 {
+        self.masks.iter().fold(0, |acc, m| acc | m)
+    }
+
+    // (i, j) のキャンディの種類 (空なら 0)
+    pub fn get(&self, i: usize, j: usize) -> usize {
+        let bit = 1 << (i * N + j);
+        (0..CANDY_KIND)
+            .find(|&c| self.masks[c] & bit != 0)
+            .map_or(0, |c| c + 1)
+    }
+
+    // t 番目のキャンディ fs[t] を p 番目の空きマスに置く
+    pub fn apply_put(&mut self, p: usize, fs: &[usize]) {
+        // 入力お菓子の置き場所の検索 (p 番目の空きビット)
+        let mut empty = !self.occupied() & FULL;
+        for _ in 1..p {
+            empty &= empty - 1;
+        }
+        let bit = empty & empty.wrapping_neg();
+        self.masks[fs[self.t] - 1] |= bit;
+    }
+
+    pub fn apply_move(&mut self, dir: char) -> Result<(), String> {
+        // 手前のマスが空いているキャンディを1マスずつ動かすのを、動かなくなるまで繰り返す
+        loop {
+            let occ = self.occupied();
+            let movable = match dir {
+                'L' => occ & !COL_FIRST & !(occ << 1),
+                'R' => occ & !COL_LAST & !(occ >> 1),
+                'F' => occ & !ROW_FIRST & !(occ << N),
+                'B' => occ & !ROW_LAST & !(occ >> N),
+                _ => {
+                    return Err(format!("Illegal output: {}", dir));
+                }
+            };
+            if movable == 0 {
+                break;
+            }
+
+            for mask in self.masks.iter_mut() {
+                let moved = *mask & movable;
+                let moved = match dir {
+                    'L' => moved >> 1,
+                    'R' => moved << 1,
+                    'F' => moved >> N,
+                    _ => moved << N,
+                };
+                *mask = (*mask & !movable) | moved;
+            }
+        }
+
+        self.t += 1;
+
+        Ok(())
+    }
+
+    // 分子部分の算出
+    pub fn compute_score(&self) -> usize {
+        // スコア計算 分子部分。種類ごとに連結成分をビットで塗り広げる
+        let mut num = 0;
+        for &mask in &self.masks {
+            let mut rest = mask;
+            while rest != 0 {
+                let mut comp = rest & rest.wrapping_neg();
+                loop {
+                    let next = (comp
+                        | ((comp >> 1) & !COL_LAST)
+                        | ((comp << 1) & !COL_FIRST)
+                        | (comp >> N)
+                        | (comp << N))
+                        & mask;
+                    if next == comp {
+                        break;
+                    }
+                    comp = next;
+                }
+                let size = comp.count_ones() as usize;
+                num += size * size;
+                rest &= !comp;
+            }
+        }
+
+        num
+    }
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// スコアの分母 (キャンディの種類ごとの個数の二乗和)
+pub fn score_den(fs: &[usize]) -> usize {
+    let mut candy_nums = [0; CANDY_KIND + 1];
+    for &f in fs {
+        candy_nums[f] += 1;
+    }
+
+    candy_nums.iter().map(|c| c * c).sum()
+}
+
+// 公式と同じ 1e6 倍して丸めたスコア
+pub fn score(fs: &[usize], st: &State) -> i64 {
+    (1e6 * st.compute_score() as f64 / score_den(fs) as f64).round() as i64
+}
+
+// seed から入力を作る => (f, p)
+pub fn gen_input(seed: u64) -> (Vec<usize>, Vec<usize>) {
+    let mut rng = Pcg64Mcg::seed_from_u64(seed);
+    let fs = (0..TURN)
+        .map(|_| rng.gen_range(1, CANDY_KIND + 1))
+        .collect();
+    let ps = (0..TURN).map(|t| rng.gen_range(1, TURN - t + 1)).collect();
+
+    (fs, ps)
+}
+
+// 公式と同じ形式 (1行目に f_1..f_100, 続いて p_1..p_100) => (f, p)
+pub fn parse_input(s: &str) -> Result<(Vec<usize>, Vec<usize>), String> {
+    let v = s
+        .split_whitespace()
+        .map(|e| {
+            e.parse::<usize>()
+                .map_err(|_| format!("invalid number: {}", e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if v.len() != 2 * TURN {
+        return Err(format!("expected {} numbers, got {}", 2 * TURN, v.len()));
+    }
+    let (fs, ps) = (v[..TURN].to_vec(), v[TURN..].to_vec());
+    if let Some(f) = fs.iter().find(|&&f| !(1..=CANDY_KIND).contains(&f)) {
+        return Err(format!("invalid candy: {}", f));
+    }
+    if let Some(t) = (0..TURN).find(|&t| !(1..=TURN - t).contains(&ps[t])) {
+        return Err(format!("invalid p at turn {}: {}", t, ps[t]));
+    }
+
+    Ok((fs, ps))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 盤面を行ごとの数字で作る
+    fn board(rows: &[&str]) -> State {
+        let mut st = State::new();
+        for (i, row) in rows.iter().enumerate() {
+            for (j, c) in row.chars().enumerate() {
+                let c = c.to_digit(10).unwrap() as usize;
+                if c != 0 {
+                    st.masks[c - 1] |= 1 << (i * N + j);
+                }
+            }
+        }
+        st
+    }
+
+    #[test]
+    fn put_fills_the_pth_empty_cell_in_row_major_order() {
+        let fs = vec![2; TURN];
+        let mut st = board(&["1010000000"]);
+        st.apply_put(2, &fs);
+        assert_eq!(st.get(0, 3), 2);
+        st.apply_put(N * N - 3, &fs);
+        assert_eq!(st.get(N - 1, N - 1), 2);
+    }
+
+    #[test]
+    fn tilt_packs_candies_toward_the_wall() {
+        let mut st = board(&["0102000003", "0000000000", "3000000000"]);
+        st.apply_move('L').unwrap();
+        assert_eq!(
+            st.masks,
+            board(&["1230000000", "0000000000", "3000000000"]).masks
+        );
+        st.apply_move('B').unwrap();
+        let mut rows = vec!["0000000000"; N - 2];
+        rows.extend(&["1000000000", "3230000000"]);
+        assert_eq!(st.masks, board(&rows).masks);
+        assert_eq!(st.t, 2);
+        assert!(st.apply_move('X').is_err());
+    }
+
+    #[test]
+    fn score_sums_squared_component_sizes() {
+        let st = board(&["1102000000", "1002000000", "0000000000"]);
+        assert_eq!(st.compute_score(), 9 + 4);
+        let fs = [vec![1; 50], vec![2; 50]].concat();
+        assert_eq!(score_den(&fs), 2 * 50 * 50);
+        assert_eq!(score(&fs, &st), (1e6 * 13.0 / 5000.0) as i64);
+    }
+
+    #[test]
+    fn parse_input_rejects_out_of_range_values() {
+        let (fs, ps) = gen_input(0);
+        let join = |fs: &[usize], ps: &[usize]| {
+            fs.iter()
+                .chain(ps)
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        assert_eq!(parse_input(&join(&fs, &ps)), Ok((fs.clone(), ps.clone())));
+        let mut bad = fs.clone();
+        bad[0] = 4;
+        assert!(parse_input(&join(&bad, &ps)).is_err());
+        let mut bad = ps.clone();
+        bad[TURN - 1] = 2;
+        assert!(parse_input(&join(&fs, &bad)).is_err());
+        assert!(parse_input("1 2 3").is_err());
+    }
+}
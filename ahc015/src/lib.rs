@@ -0,0 +1,4 @@
+// ソルバー (src/bin/a.rs) とローカルツール (judge, replay) で共有するもの
+// 提出するときは bundle で a.rs に埋め込む
+
+pub mod game;
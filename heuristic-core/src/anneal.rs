@@ -0,0 +1,345 @@
+// 焼きなまし法の枠組み
+// 問題ごとに Annealing (近傍の提案・差分・適用・取り消し・最良解の保存) を実装し、
+// 温度の下げ方 (Schedule) と打ち切り (Budget) を選んで Annealer::run に渡す。スコアは最大化する
+
+use crate::rng::Rng;
+pub use crate::timer::Budget;
+
+pub trait Annealing {
+    type Move;
+    type Snapshot;
+
+    fn score(&self) -> f64;
+
+    // 近傍を1つ作る (作れなければ None)
+    fn propose(&self, rng: &mut Rng) -> Option<Self::Move>;
+
+    // 適用したときのスコアの増分。差分計算できないなら適用して測って戻す
+    fn delta(&mut self, mv: &Self::Move) -> f64 {
+        let before = self.score();
+        self.apply(mv);
+        let after = self.score();
+        self.revert(mv);
+        after - before
+    }
+
+    fn apply(&mut self, mv: &Self::Move);
+    fn revert(&mut self, mv: &Self::Move);
+
+    // 最良解の保存と復元
+    fn snapshot(&self) -> Self::Snapshot;
+    fn restore(&mut self, snapshot: Self::Snapshot);
+}
+
+// 進み具合 ([0, 1]) から温度を決める
+pub trait Schedule {
+    fn temp(&self, progress: f64) -> f64;
+}
+
+pub struct Linear {
+    pub start: f64,
+    pub end: f64,
+}
+impl Schedule for Linear {
+    fn temp(&self, progress: f64) -> f64 {
+        self.start + (self.end - self.start) * progress
+    }
+}
+
+pub struct Exponential {
+    pub start: f64,
+    pub end: f64,
+}
+impl Schedule for Exponential {
+    fn temp(&self, progress: f64) -> f64 {
+        self.start * (self.end / self.start).powf(progress)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    pub iterations: usize,
+    pub proposed: usize, // propose が Some を返した数
+    pub accepted: usize,
+    pub best_updates: usize,
+    pub rounds: usize,
+    pub best_score: f64,
+    pub temp: f64,
+    pub progress: f64,
+}
+
+pub struct Annealer<S: Schedule> {
+    schedule: S,
+    budget: Budget,
+    interval: usize, // 時計と温度を見直す間隔 (反復数)
+}
+
+impl<S: Schedule> Annealer<S> {
+    pub fn new(schedule: S, budget: Budget) -> Self {
+        Self {
+            schedule,
+            budget,
+            interval: 1000,
+        }
+    }
+
+    pub fn interval(mut self, interval: usize) -> Self {
+        self.interval = interval.max(1);
+        self
+    }
+
+    // interval 回ごとに hook を呼ぶ。終わったら最良解に戻す
+    pub fn run<A: Annealing>(
+        &self,
+        state: &mut A,
+        rng: &mut Rng,
+        mut hook: impl FnMut(&A, &Stats),
+    ) -> Stats {
+        let timer = self.budget.timer();
+        let mut stats = Stats {
+            best_score: state.score(),
+            ..Stats::default()
+        };
+        let mut best = state.snapshot();
+
+        loop {
+            stats.progress = match (&timer, &self.budget) {
+                (Some(timer), _) => timer.progress(),
+                (None, &Budget::Iterations(n)) => {
+                    (stats.iterations as f64 / n.max(1) as f64).min(1.0)
+                }
+                _ => unreachable!(),
+            };
+            if stats.progress >= 1.0 {
+                break;
+            }
+            stats.temp = self.schedule.temp(stats.progress);
+
+            let round = match self.budget {
+                Budget::Iterations(n) => self.interval.min(n - stats.iterations),
+                _ => self.interval,
+            };
+            for _ in 0..round {
+                stats.iterations += 1;
+                let mv = match state.propose(rng) {
+                    Some(mv) => mv,
+                    None => continue,
+                };
+                stats.proposed += 1;
+
+                // スコアが増すか、`e^(score差 / T)` の確率にヒットしたら受け入れる
+                let diff = state.delta(&mv);
+                if diff >= 0.0 || rng.gen_bool(f64::exp(diff / stats.temp)) {
+                    state.apply(&mv);
+                    stats.accepted += 1;
+
+                    let score = state.score();
+                    if score > stats.best_score {
+                        stats.best_score = score;
+                        stats.best_updates += 1;
+                        best = state.snapshot();
+                    }
+                }
+            }
+
+            stats.rounds += 1;
+            hook(state, &stats);
+        }
+
+        state.restore(best);
+
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 各 x_i を target_i に近づける (最適値 0)
+    struct Toy {
+        xs: Vec<i64>,
+        target: Vec<i64>,
+        score: f64,
+    }
+
+    impl Toy {
+        fn new(target: Vec<i64>) -> Self {
+            let xs = vec![0; target.len()];
+            let score = -target.iter().map(|t| (t * t) as f64).sum::<f64>();
+            Self { xs, target, score }
+        }
+
+        fn cost(&self, i: usize, x: i64) -> f64 {
+            ((x - self.target[i]) * (x - self.target[i])) as f64
+        }
+    }
+
+    impl Annealing for Toy {
+        type Move = (usize, i64);
+        type Snapshot = (Vec<i64>, f64);
+
+        fn score(&self) -> f64 {
+            self.score
+        }
+
+        fn propose(&self, rng: &mut Rng) -> Option<Self::Move> {
+            let i = rng.gen_range(0, self.xs.len());
+            let d = if rng.gen_bool(0.5) { 1 } else { -1 };
+            Some((i, d))
+        }
+
+        fn delta(&mut self, &(i, d): &Self::Move) -> f64 {
+            self.cost(i, self.xs[i]) - self.cost(i, self.xs[i] + d)
+        }
+
+        fn apply(&mut self, mv: &Self::Move) {
+            self.score += self.delta(mv);
+            self.xs[mv.0] += mv.1;
+        }
+
+        fn revert(&mut self, &(i, d): &Self::Move) {
+            self.xs[i] -= d;
+            self.score += self.cost(i, self.xs[i] + d) - self.cost(i, self.xs[i]);
+        }
+
+        fn snapshot(&self) -> Self::Snapshot {
+            (self.xs.clone(), self.score)
+        }
+
+        fn restore(&mut self, (xs, score): Self::Snapshot) {
+            self.xs = xs;
+            self.score = score;
+        }
+    }
+
+    #[test]
+    fn schedules_interpolate_between_endpoints() {
+        let lin = Linear {
+            start: 10.0,
+            end: 2.0,
+        };
+        assert_eq!(lin.temp(0.0), 10.0);
+        assert_eq!(lin.temp(0.5), 6.0);
+        assert_eq!(lin.temp(1.0), 2.0);
+
+        let exp = Exponential {
+            start: 100.0,
+            end: 1.0,
+        };
+        assert!((exp.temp(0.5) - 10.0).abs() < 1e-9);
+        assert!((exp.temp(1.0) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn finds_the_optimum_of_a_toy_problem() {
+        let mut toy = Toy::new(vec![5, -3, 12, 0, 7, -9]);
+        let mut rng = Rng::new(0);
+        let annealer = Annealer::new(
+            Exponential {
+                start: 10.0,
+                end: 0.01,
+            },
+            Budget::Iterations(20_000),
+        )
+        .interval(100);
+
+        let mut rounds = 0;
+        let stats = annealer.run(&mut toy, &mut rng, |_, _| rounds += 1);
+
+        assert_eq!(toy.xs, toy.target);
+        assert_eq!(toy.score(), 0.0);
+        assert_eq!(stats.best_score, 0.0);
+        assert_eq!(stats.iterations, 20_000);
+        assert_eq!(stats.rounds, 200);
+        assert_eq!(rounds, 200);
+        assert!(stats.accepted <= stats.proposed);
+    }
+
+    #[test]
+    fn restores_the_best_state_at_the_end() {
+        // 高温のままだと最後は最良から離れるが、戻した状態は最良と一致する
+        let mut toy = Toy::new(vec![3; 4]);
+        let mut rng = Rng::new(1);
+        let annealer = Annealer::new(
+            Linear {
+                start: 1e6,
+                end: 1e6,
+            },
+            Budget::Iterations(5_000),
+        );
+
+        let stats = annealer.run(&mut toy, &mut rng, |_, _| {});
+        let recomputed = -toy
+            .xs
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| toy.cost(i, x))
+            .sum::<f64>();
+        assert_eq!(toy.score(), stats.best_score);
+        assert_eq!(recomputed, stats.best_score);
+    }
+
+    #[test]
+    fn fake_timer_makes_time_budget_reproducible() {
+        let run = || {
+            let mut toy = Toy::new(vec![40, -25, 7]);
+            // 時計を読むのは interval 回に1回なので、50 回読んだところ (49 周) で終わる
+            let annealer = Annealer::new(
+                Linear {
+                    start: 5.0,
+                    end: 0.1,
+                },
+                Budget::Timer(crate::timer::Timer::fake(50, 1.0)),
+            )
+            .interval(100);
+            let stats = annealer.run(&mut toy, &mut Rng::new(7), |_, _| {});
+            (toy.xs, stats.iterations, stats.accepted)
+        };
+
+        let (xs, iterations, accepted) = run();
+        assert_eq!(iterations, 4_900);
+        assert_eq!(run(), (xs, iterations, accepted));
+    }
+
+    #[test]
+    fn default_delta_applies_and_reverts() {
+        struct Counter(i64);
+        impl Annealing for Counter {
+            type Move = i64;
+            type Snapshot = i64;
+            fn score(&self) -> f64 {
+                self.0 as f64
+            }
+            fn propose(&self, _: &mut Rng) -> Option<i64> {
+                Some(1)
+            }
+            fn apply(&mut self, mv: &i64) {
+                self.0 += mv;
+            }
+            fn revert(&mut self, mv: &i64) {
+                self.0 -= mv;
+            }
+            fn snapshot(&self) -> i64 {
+                self.0
+            }
+            fn restore(&mut self, s: i64) {
+                self.0 = s;
+            }
+        }
+
+        let mut c = Counter(3);
+        assert_eq!(c.delta(&2), 2.0);
+        assert_eq!(c.0, 3);
+
+        let annealer = Annealer::new(
+            Linear {
+                start: 1.0,
+                end: 1.0,
+            },
+            Budget::Time(0),
+        );
+        let stats = annealer.run(&mut c, &mut Rng::new(0), |_, _| {});
+        assert_eq!(stats.iterations, 0);
+    }
+}
@@ -0,0 +1,364 @@
+// ビームサーチの枠組み
+// 問題ごとに Beam (次の状態の列挙・評価・同一視の鍵) を実装し、BeamSearch::run に渡す。評価は最大化する
+
+pub use crate::timer::Budget;
+use std::collections::HashSet;
+use std::hash::Hash;
+
+pub trait Beam: Sized {
+    type Action: Clone;
+    type Key: Hash + Eq;
+
+    // (行動, 行動後の状態) を列挙する。空なら終端
+    fn successors(&self) -> Vec<(Self::Action, Self)>;
+
+    fn evaluate(&self) -> f64;
+
+    // 同じ鍵の状態は評価の良い1つだけ残す
+    fn key(&self) -> Self::Key;
+}
+
+#[derive(Debug, Clone)]
+pub struct BeamResult<S, A> {
+    pub state: S,
+    pub actions: Vec<A>,
+    pub score: f64,
+    pub depth: usize,    // 展開し終えた深さ
+    pub expanded: usize, // 列挙した状態の数
+    pub timed_out: bool, // budget で打ち切った
+}
+
+fn is_better<S>(best: &Option<(f64, S, usize)>, score: f64) -> bool {
+    match best {
+        Some(best) => score > best.0,
+        None => true,
+    }
+}
+
+pub struct BeamSearch {
+    width: usize,
+    depth: usize,
+    budget: Option<Budget>, // None なら depth まで
+    dedup: bool,
+}
+
+impl BeamSearch {
+    pub fn new(width: usize, depth: usize) -> Self {
+        Self {
+            width: width.max(1),
+            depth,
+            budget: None,
+            dedup: true,
+        }
+    }
+
+    // 時間か、列挙する状態の数 (Budget::Iterations) で打ち切る。
+    // 深さの途中で打ち切ったときは、そこまでの候補と展開しなかった状態から選ぶ
+    pub fn budget(mut self, budget: Budget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    pub fn dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    // 最後のビームと途中で終端した状態のうち、評価が最も良いものを返す
+    pub fn run<S: Beam>(&self, init: S) -> BeamResult<S, S::Action> {
+        let timer = self.budget.as_ref().and_then(|budget| budget.timer());
+        let is_over = |expanded: usize| match (&timer, &self.budget) {
+            (Some(timer), _) => timer.is_over(),
+            (None, Some(Budget::Iterations(n))) => expanded >= *n,
+            _ => false,
+        };
+
+        // 行動列の復元用 (親の番号, 行動)
+        let mut nodes: Vec<(usize, Option<S::Action>)> = vec![(!0, None)];
+        let score = init.evaluate();
+        let mut beam = vec![(score, init, 0)];
+        let mut finished: Option<(f64, S, usize)> = None;
+        let mut depth = 0;
+        let mut expanded = 0;
+        let mut timed_out = false;
+        let mut rest = vec![]; // 打ち切りで展開しなかった状態
+
+        while depth < self.depth && !beam.is_empty() {
+            let mut candidates = vec![];
+            let mut states = beam.into_iter();
+            for (score, st, node) in &mut states {
+                if is_over(expanded) {
+                    timed_out = true;
+                    rest.push((score, st, node));
+                    break;
+                }
+                let next = st.successors();
+                if next.is_empty() {
+                    if is_better(&finished, score) {
+                        finished = Some((score, st, node));
+                    }
+                    continue;
+                }
+                for (action, next_st) in next {
+                    expanded += 1;
+                    candidates.push((next_st.evaluate(), next_st, node, action));
+                }
+            }
+            rest.extend(states);
+            if candidates.is_empty() {
+                beam = vec![];
+                break;
+            }
+
+            candidates.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+            let mut keys = HashSet::new();
+            beam = vec![];
+            for (score, st, parent, action) in candidates {
+                if beam.len() == self.width {
+                    break;
+                }
+                if self.dedup && !keys.insert(st.key()) {
+                    continue;
+                }
+                nodes.push((parent, Some(action)));
+                beam.push((score, st, nodes.len() - 1));
+            }
+            if timed_out {
+                break;
+            }
+            depth += 1;
+        }
+
+        let mut best = finished;
+        for (score, st, node) in beam.into_iter().chain(rest) {
+            if is_better(&best, score) {
+                best = Some((score, st, node));
+            }
+        }
+        let (score, state, mut node) = best.unwrap();
+
+        let mut actions = vec![];
+        while let Some(action) = &nodes[node].1 {
+            actions.push(action.clone());
+            node = nodes[node].0;
+        }
+        actions.reverse();
+
+        BeamResult {
+            state,
+            actions,
+            score,
+            depth,
+            expanded,
+            timed_out,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0/1 ナップサック。(何番目まで決めたか, 重さ) で同一視すると DP と同じになる
+    #[derive(Clone)]
+    struct Knapsack<'a> {
+        items: &'a [(usize, usize)], // (重さ, 価値)
+        cap: usize,
+        i: usize,
+        weight: usize,
+        value: usize,
+    }
+
+    impl<'a> Beam for Knapsack<'a> {
+        type Action = bool;
+        type Key = (usize, usize);
+
+        fn successors(&self) -> Vec<(bool, Self)> {
+            if self.i == self.items.len() {
+                return vec![];
+            }
+            let (w, v) = self.items[self.i];
+            let mut res = vec![(
+                false,
+                Knapsack {
+                    i: self.i + 1,
+                    ..self.clone()
+                },
+            )];
+            if self.weight + w <= self.cap {
+                res.push((
+                    true,
+                    Knapsack {
+                        i: self.i + 1,
+                        weight: self.weight + w,
+                        value: self.value + v,
+                        ..self.clone()
+                    },
+                ));
+            }
+            res
+        }
+
+        fn evaluate(&self) -> f64 {
+            self.value as f64
+        }
+
+        fn key(&self) -> (usize, usize) {
+            (self.i, self.weight)
+        }
+    }
+
+    fn knapsack_brute_force(items: &[(usize, usize)], cap: usize) -> usize {
+        (0..1usize << items.len())
+            .filter_map(|mask| {
+                let (w, v) = (0..items.len())
+                    .filter(|&i| mask >> i & 1 == 1)
+                    .fold((0, 0), |(w, v), i| (w + items[i].0, v + items[i].1));
+                if w <= cap {
+                    Some(v)
+                } else {
+                    None
+                }
+            })
+            .max()
+            .unwrap()
+    }
+
+    const ITEMS: [(usize, usize); 12] = [
+        (7, 10),
+        (3, 4),
+        (9, 13),
+        (2, 3),
+        (8, 9),
+        (5, 8),
+        (4, 5),
+        (6, 9),
+        (1, 1),
+        (10, 14),
+        (3, 5),
+        (5, 6),
+    ];
+
+    fn knapsack(cap: usize) -> Knapsack<'static> {
+        Knapsack {
+            items: &ITEMS,
+            cap,
+            i: 0,
+            weight: 0,
+            value: 0,
+        }
+    }
+
+    #[test]
+    fn dedup_by_weight_finds_the_knapsack_optimum() {
+        for cap in [5, 13, 20, 31] {
+            let res = BeamSearch::new(cap + 1, ITEMS.len()).run(knapsack(cap));
+            assert_eq!(res.state.value, knapsack_brute_force(&ITEMS, cap));
+
+            // 復元した行動列をたどると同じ解になる
+            let (w, v) = res
+                .actions
+                .iter()
+                .zip(ITEMS.iter())
+                .filter(|(&take, _)| take)
+                .fold((0, 0), |(w, v), (_, item)| (w + item.0, v + item.1));
+            assert_eq!(res.actions.len(), ITEMS.len());
+            assert!(w <= cap);
+            assert_eq!(v, res.state.value);
+            assert_eq!(res.score, v as f64);
+        }
+    }
+
+    #[test]
+    fn full_width_without_dedup_is_exhaustive() {
+        let cap = 17;
+        let res = BeamSearch::new(1 << ITEMS.len(), ITEMS.len())
+            .dedup(false)
+            .run(knapsack(cap));
+        assert_eq!(res.state.value, knapsack_brute_force(&ITEMS, cap));
+    }
+
+    // 目標の文字列に先頭から何文字一致しているか
+    #[derive(Clone)]
+    struct Word(String);
+    const TARGET: &str = "beamsearch";
+
+    impl Beam for Word {
+        type Action = char;
+        type Key = String;
+
+        fn successors(&self) -> Vec<(char, Self)> {
+            if self.0.len() == TARGET.len() {
+                return vec![];
+            }
+            ('a'..='z')
+                .map(|c| (c, Word(format!("{}{}", self.0, c))))
+                .collect()
+        }
+
+        fn evaluate(&self) -> f64 {
+            self.0
+                .chars()
+                .zip(TARGET.chars())
+                .take_while(|(a, b)| a == b)
+                .count() as f64
+        }
+
+        fn key(&self) -> String {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn reconstructs_the_action_sequence() {
+        let res = BeamSearch::new(3, 100).run(Word(String::new()));
+        assert_eq!(res.actions.iter().collect::<String>(), TARGET);
+        assert_eq!(res.state.0, TARGET);
+        // 終端に達したら深さの上限より前に止まる
+        assert_eq!(res.depth, TARGET.len());
+        assert!(!res.timed_out);
+    }
+
+    #[test]
+    fn depth_limit_and_time_limit_stop_early() {
+        let res = BeamSearch::new(3, 4).run(Word(String::new()));
+        assert_eq!(res.state.0, "beam");
+        assert_eq!(res.depth, 4);
+
+        let res = BeamSearch::new(3, 100)
+            .budget(Budget::Time(0))
+            .run(Word(String::new()));
+        assert!(res.timed_out);
+        assert_eq!(res.depth, 0);
+        assert!(res.actions.is_empty());
+        assert_eq!(res.expanded, 0);
+    }
+
+    #[test]
+    fn budget_stops_in_the_middle_of_a_depth() {
+        // 深さ1で 26 個。深さ2は先頭の状態を展開したところで 30 を超える
+        let res = BeamSearch::new(3, 100)
+            .budget(Budget::Iterations(30))
+            .run(Word(String::new()));
+        assert!(res.timed_out);
+        assert_eq!(res.expanded, 52);
+        assert_eq!(res.depth, 1);
+        // 展開できた先頭の状態の子が選ばれる
+        assert_eq!(res.state.0, "be");
+        assert_eq!(res.actions, vec!['b', 'e']);
+    }
+
+    #[test]
+    fn fake_timer_makes_the_budget_reproducible() {
+        let run = || {
+            BeamSearch::new(3, 100)
+                .budget(Budget::Timer(crate::timer::Timer::fake(5, 1.0)))
+                .run(Word(String::new()))
+        };
+        let (a, b) = (run(), run());
+        assert!(a.timed_out);
+        assert!(a.depth < TARGET.len());
+        assert_eq!(a.state.0, b.state.0);
+        assert_eq!(a.expanded, b.expanded);
+    }
+}
@@ -0,0 +1,61 @@
+// AHC001: 入力生成と採点、可視化は公式ツール (tools/) を使う。my_vis は out.svg を書く
+
+use crate::contest::{from_tools, Contest};
+use heuristic_core::harness::{expand, Job};
+use std::path::Path;
+
+pub struct Ahc001;
+
+// 公式の gen の作業場所。公式の tools/seeds.txt と tools/in はそのまま残す
+const GEN_DIR: &str = "tools/gen";
+
+impl Contest for Ahc001 {
+    fn name(&self) -> &'static str {
+        "ahc001"
+    }
+
+    // 公式の gen は seeds.txt の i 行目の seed から in/{i:04}.txt を作るので、
+    // ファイル名と seed が揃うよう 0..=to を GEN_DIR で作り、まだ無い from..=to だけ tools/in に写す
+    fn gen(&self, from: u64, to: u64, _bin: &Path) -> Result<(), String> {
+        let seeds = (0..=to).map(|s| format!("{}\n", s)).collect::<String>();
+        let path = format!("{}/seeds.txt", GEN_DIR);
+        std::fs::create_dir_all(GEN_DIR)
+            .and_then(|_| std::fs::write(&path, seeds))
+            .map_err(|e| format!("{}: {}", path, e))?;
+        Job::new("cargo")
+            .args(&["run", "-q", "--release", "--bin", "gen", "seeds.txt"])
+            .dir(GEN_DIR)
+            .status(None)?;
+
+        for seed in from..=to {
+            let dst = expand("tools/in/{case}.txt", seed);
+            if Path::new(&dst).exists() {
+                continue;
+            }
+            let src = expand(&format!("{}/in/{{case}}.txt", GEN_DIR), seed);
+            std::fs::copy(&src, &dst).map_err(|e| format!("{}: {}", src, e))?;
+        }
+
+        Ok(())
+    }
+
+    fn run(&self, solver: &str, out: &str) -> Job {
+        Job::new(solver).stdin("tools/in/{case}.txt").stdout(out)
+    }
+
+    fn eval(&self, out: &str) -> Option<Job> {
+        Some(
+            Job::new("cargo")
+                .args(&["run", "-q", "--release", "--bin", "my_vis"])
+                .args(&["in/{case}.txt", &from_tools(out)])
+                .dir("tools"),
+        )
+    }
+
+    fn vis(&self) -> Option<(Job, String)> {
+        Some((
+            self.eval("tools/out/{case}.txt")?,
+            "tools/out.svg".to_string(),
+        ))
+    }
+}
@@ -0,0 +1,41 @@
+// AHC012: 入力は自前の gen、採点と可視化は公式ツール (tools/) の vis を使う
+
+use crate::contest::{from_tools, resolve, Contest};
+use heuristic_core::harness::Job;
+use std::path::Path;
+
+pub struct Ahc012;
+
+impl Contest for Ahc012 {
+    fn name(&self) -> &'static str {
+        "ahc012"
+    }
+
+    // gen は既にある入力を上書きしない
+    fn gen(&self, from: u64, to: u64, bin: &Path) -> Result<(), String> {
+        let job = Job::new("{bin}/gen")
+            .args(&[&from.to_string(), &to.to_string()])
+            .args(&["--dir", "tools/in"]);
+        resolve(&job, bin).status(None)
+    }
+
+    fn run(&self, solver: &str, out: &str) -> Job {
+        Job::new(solver).stdin("tools/in/{case}.txt").stdout(out)
+    }
+
+    fn eval(&self, out: &str) -> Option<Job> {
+        Some(
+            Job::new("cargo")
+                .args(&["run", "-q", "--release", "--bin", "vis"])
+                .args(&["in/{case}.txt", &from_tools(out)])
+                .dir("tools"),
+        )
+    }
+
+    fn vis(&self) -> Option<(Job, String)> {
+        Some((
+            self.eval("tools/out/{case}.txt")?,
+            "tools/out.svg".to_string(),
+        ))
+    }
+}
@@ -0,0 +1,39 @@
+// AHC015: 対話型。入力は judge が seed から作り、スコアも judge が出す。
+// 出力の代わりにソルバーのゲームログ (AHC015_LOG) を書き、replay で HTML にする
+
+use crate::contest::Contest;
+use heuristic_core::harness::Job;
+use std::path::Path;
+
+pub struct Ahc015;
+
+impl Contest for Ahc015 {
+    fn name(&self) -> &'static str {
+        "ahc015"
+    }
+
+    fn input(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn gen(&self, _from: u64, _to: u64, _bin: &Path) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn run(&self, solver: &str, out: &str) -> Job {
+        Job::new("{bin}/judge")
+            .args(&["--seed", "{seed}", solver])
+            .env("AHC015_LOG", out)
+    }
+
+    fn eval(&self, _out: &str) -> Option<Job> {
+        None
+    }
+
+    fn vis(&self) -> Option<(Job, String)> {
+        Some((
+            Job::new("{bin}/replay").args(&["tools/out/{case}.txt", "tools/out/{case}.html"]),
+            "tools/out/{case}.html".to_string(),
+        ))
+    }
+}
@@ -0,0 +1,56 @@
+// コンテストごとの違い
+// Job はコンテストのディレクトリから sh を通さずに動かす。{bin} をビルドしたバイナリのディレクトリに、
+// {seed} を seed に、{case} を4桁に0埋めした seed に置き換える
+
+use crate::{ahc001::Ahc001, ahc012::Ahc012, ahc015::Ahc015};
+use heuristic_core::harness::Job;
+use std::path::Path;
+
+pub trait Contest {
+    fn name(&self) -> &'static str;
+
+    // seed の入力ファイル。自己ベストは入力のハッシュと一緒に記録する。入力ファイルを使わないなら None
+    fn input(&self) -> Option<&'static str> {
+        Some("tools/in/{case}.txt")
+    }
+
+    // from..=to の入力を input() に作る。既にある入力は上書きしない (input() が None なら呼ばない)
+    fn gen(&self, from: u64, to: u64, bin: &Path) -> Result<(), String>;
+
+    // solver で1つの seed を解き、出力を out に書いてスコアを出す
+    fn run(&self, solver: &str, out: &str) -> Job;
+
+    // run が out に書いた出力を公式と同じ計算で採点する。
+    // これがあるコンテストは、run のスコアもソルバーの自己申告ではなくこちらで読む
+    fn eval(&self, out: &str) -> Option<Job>;
+
+    // 1つの seed を可視化するジョブと、できあがるファイル
+    fn vis(&self) -> Option<(Job, String)>;
+
+    // 1つの seed を、ソルバーのログを全部見ながら動かす
+    fn judge(&self) -> Job {
+        self.run(&format!("{{bin}}/{}", self.name()), "tools/out/{case}.txt")
+    }
+}
+
+// コンテストのディレクトリからのパスを tools からのパスにする
+pub fn from_tools(path: &str) -> String {
+    if Path::new(path).is_absolute() {
+        path.to_string()
+    } else {
+        format!("../{}", path)
+    }
+}
+
+// {bin} を置き換える
+pub fn resolve(job: &Job, bin: &Path) -> Job {
+    job.map(|s| s.replace("{bin}", &bin.display().to_string()))
+}
+
+pub fn all() -> Vec<Box<dyn Contest>> {
+    vec![Box::new(Ahc001), Box::new(Ahc012), Box::new(Ahc015)]
+}
+
+pub fn find(name: &str) -> Option<Box<dyn Contest>> {
+    all().into_iter().find(|c| c.name() == name)
+}
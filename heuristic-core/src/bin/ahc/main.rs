@@ -0,0 +1,446 @@
+// コンテストごとの入力生成、実行、採点、可視化、比較をまとめたコマンド (macOS 以外でも動くよう、シェルスクリプトは使わない)
+// Usage: ahc <contest> <command> [args...]
+//   contest : ahc001 | ahc012 | ahc015 (コンテストごとの違いは contest::Contest)
+//   gen     [FROM TO]                       入力を tools/in に作る (既にある入力は上書きしない)
+//   run     [FROM TO] [--jobs J] [--params P]
+//                                           まとめて解いて tools/out に書き、スコアと自己ベストに対する相対スコアを表示する
+//                                           (eval のあるコンテストは、スコアを公式と同じ計算で読む)
+//                                           (自己ベストはワークスペース直下の ledger.txt に記録する)
+//   eval    [FROM TO]                       tools/out の出力を公式と同じ計算で採点し直す
+//   vis     [SEED]                          1つの seed を可視化して、できたファイルのパスを表示する
+//   judge   [SEED] [--params P]             1つの seed を、ソルバーのログを全部見ながら動かす
+//   compare [FROM TO] [--jobs J] [--params-a P] [--params-b P] [--solver-b PATH]
+//                                           同じソルバーの2つのパラメータ、または別にビルドしたソルバーと比べる
+//                                           (スコアの読み方は run と同じ)
+//   seed の範囲は省くと 0 10、SEED は 0。P は環境変数 PARAMS として渡す "name=value,..."
+//   コマンドはコンテストのディレクトリで sh を通さずに動かす。動かす前にそのクレートをビルドする
+//
+// 例: cargo run --release --bin ahc -- ahc012 run 0 49 --jobs 4
+
+mod ahc001;
+mod ahc012;
+mod ahc015;
+mod contest;
+
+use contest::Contest;
+use heuristic_core::harness::{expand, par_map, parse_score, Job};
+use heuristic_core::ledger::{read_input, report, Ledger, Trial};
+use heuristic_core::stats::{pair_report, SeedPair};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const DEFAULT_SEEDS: (u64, u64) = (0, 10);
+const LEDGER_FILE: &str = "ledger.txt";
+const OUT: &str = "tools/out/{case}.txt"; // run の出力
+const TOLERANCE: f64 = 0.01; // 自己ベストからこの割合より下がった seed を目立たせる
+
+struct Args {
+    seeds: Vec<u64>,                // 位置引数 (seed)
+    options: Vec<(String, String)>, // --name value
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Option<Self> {
+        let mut seeds = vec![];
+        let mut options = vec![];
+        let mut i = 0;
+        while i < args.len() {
+            if args[i].starts_with("--") {
+                options.push((args[i].clone(), args.get(i + 1)?.clone()));
+                i += 2;
+            } else {
+                seeds.push(args[i].parse().ok()?);
+                i += 1;
+            }
+        }
+
+        Some(Self { seeds, options })
+    }
+
+    fn range(&self) -> Option<(u64, u64)> {
+        match self.seeds.as_slice() {
+            [] => Some(DEFAULT_SEEDS),
+            &[from, to] if from <= to => Some((from, to)),
+            _ => None,
+        }
+    }
+
+    fn seed(&self) -> Option<u64> {
+        match self.seeds.as_slice() {
+            [] => Some(0),
+            &[seed] => Some(seed),
+            _ => None,
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    // 知っている名前だけか
+    fn only(&self, names: &[&str]) -> bool {
+        self.options
+            .iter()
+            .all(|(n, _)| names.contains(&n.as_str()))
+    }
+}
+
+// 1つの seed を解いて採点する
+struct Task {
+    solve: Job,
+    eval: Option<Job>, // 公式と同じ採点。無ければソルバーの出力からスコアを読む
+    params: Option<String>,
+}
+
+struct Driver {
+    contest: Box<dyn Contest>,
+    root: PathBuf, // ワークスペース
+    bin: PathBuf,  // ビルドしたバイナリの置き場所 (このコマンドと同じ)
+}
+
+impl Driver {
+    fn job(&self, job: Job) -> Job {
+        contest::resolve(&job, &self.bin)
+    }
+
+    // このコマンドと同じ profile で、コンテストのクレートをビルドする
+    fn build(&self) {
+        let mut cmd = Command::new("cargo");
+        cmd.current_dir(&self.root)
+            .args(["build", "-q", "-p", self.contest.name()]);
+        if !cfg!(debug_assertions) {
+            cmd.arg("--release");
+        }
+        // 警告で毎回埋まらないよう、失敗したときだけ cargo の出力を見せる
+        match cmd.output() {
+            Ok(out) if out.status.success() => {}
+            Ok(out) => {
+                eprint!("{}", String::from_utf8_lossy(&out.stderr));
+                fail("build failed");
+            }
+            Err(e) => fail(&format!("cannot run cargo: {}", e)),
+        }
+    }
+
+    fn solver(&self) -> String {
+        format!("{{bin}}/{}", self.contest.name())
+    }
+
+    // solver で seed を解いて out に書くタスク
+    fn task(&self, solver: &str, out: &str, seed: u64, params: Option<&str>) -> Task {
+        Task {
+            solve: self.job(self.contest.run(solver, out)).expand(seed),
+            eval: self.contest.eval(out).map(|job| self.job(job).expand(seed)),
+            params: params.map(|p| p.to_string()),
+        }
+    }
+
+    // jobs 並列で解いて採点し、出力 (標準出力と標準エラー) からスコアを読む
+    fn scores(&self, tasks: Vec<Task>, jobs: usize) -> Vec<Option<f64>> {
+        par_map(tasks, jobs, |task| {
+            let out = task.solve.output(task.params.as_deref());
+            let out = match &task.eval {
+                Some(eval) => out.and_then(|_| eval.output(None)),
+                None => out,
+            };
+            match out {
+                Ok(out) => parse_score(&out, None),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    None
+                }
+            }
+        })
+    }
+
+    fn gen(&self, (from, to): (u64, u64)) {
+        if self.contest.input().is_none() {
+            println!("{}: inputs are generated from seeds", self.contest.name());
+            return;
+        }
+        create_dir("tools/in");
+        if let Err(e) = self.contest.gen(from, to, &self.bin) {
+            fail(&format!("gen failed: {}", e));
+        }
+    }
+
+    fn run(&self, (from, to): (u64, u64), jobs: usize, params: Option<&str>) {
+        create_dir("tools/out");
+        let seeds = (from..=to).collect::<Vec<_>>();
+        let tasks = seeds
+            .iter()
+            .map(|&seed| self.task(&self.solver(), OUT, seed, params))
+            .collect();
+        let scores = self.scores(tasks, jobs);
+
+        let trials = seeds
+            .into_iter()
+            .zip(scores)
+            .map(|(seed, score)| Trial {
+                seed,
+                input: match self.contest.input() {
+                    Some(input) => read_input(&expand(input, seed)).map(Some),
+                    None => Ok(None),
+                },
+                score,
+                output: std::fs::read_to_string(expand(OUT, seed)).ok(),
+            })
+            .collect();
+        let file = self.root.join(LEDGER_FILE).display().to_string();
+        let mut ledger = Ledger::load(&file).unwrap_or_else(|e| fail(&e));
+        let rows = ledger.update(self.contest.name(), trials, false);
+        if let Err(e) = ledger.save(&file) {
+            fail(&e);
+        }
+        print!("{}", report(&rows, false, TOLERANCE));
+    }
+
+    fn eval(&self, (from, to): (u64, u64)) {
+        let job = match self.contest.eval(OUT) {
+            Some(job) => self.job(job),
+            None => fail(&format!("{}: scores come from run", self.contest.name())),
+        };
+
+        let mut scores = vec![];
+        println!("{:>6}  {:>14}", "seed", "score");
+        for seed in from..=to {
+            let out = job.expand(seed).output(None).unwrap_or_else(|e| e);
+            match parse_score(&out, None) {
+                Some(score) => {
+                    println!("{:>6}  {:>14.3}", seed, score);
+                    scores.push(score);
+                }
+                None => {
+                    // 採点できなかった理由 (出力の最後の行) を添える
+                    let reason = out.lines().rev().find(|l| !l.trim().is_empty());
+                    println!("{:>6}  {:>14}  {}", seed, "-", reason.unwrap_or(""));
+                }
+            }
+        }
+        if !scores.is_empty() {
+            println!(
+                "mean: {:.3} ({} / {} seeds)",
+                scores.iter().sum::<f64>() / scores.len() as f64,
+                scores.len(),
+                to - from + 1
+            );
+        }
+    }
+
+    fn vis(&self, seed: u64) {
+        let (job, file) = match self.contest.vis() {
+            Some(vis) => vis,
+            None => fail(&format!("{}: no visualizer", self.contest.name())),
+        };
+        if let Err(e) = self.job(job).expand(seed).status(None) {
+            fail(&format!("vis failed: {}", e));
+        }
+        let path = std::env::current_dir().unwrap().join(expand(&file, seed));
+        println!("{}", path.display());
+    }
+
+    fn judge(&self, seed: u64, params: Option<&str>) {
+        create_dir("tools/out");
+        if let Err(e) = self.job(self.contest.judge()).expand(seed).status(params) {
+            fail(&format!("judge failed: {}", e));
+        }
+    }
+
+    // A と B を同じ seed で交互に動かし、seed ごとの差と対応のある比較を表示する
+    fn compare(&self, (from, to): (u64, u64), jobs: usize, args: &Args) {
+        create_dir("tools/out/a");
+        create_dir("tools/out/b");
+        let solver_b = args
+            .get("--solver-b")
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| self.solver());
+        let sides = [
+            (
+                self.solver(),
+                "tools/out/a/{case}.txt",
+                args.get("--params-a"),
+            ),
+            (solver_b, "tools/out/b/{case}.txt", args.get("--params-b")),
+        ];
+
+        let seeds = (from..=to).collect::<Vec<_>>();
+        let mut tasks = vec![];
+        for &seed in &seeds {
+            for (solver, out, params) in &sides {
+                tasks.push(self.task(solver, out, seed, *params));
+            }
+        }
+        let scores = self.scores(tasks, jobs);
+
+        let pairs = seeds
+            .into_iter()
+            .zip(scores.chunks(2))
+            .map(|(seed, s)| SeedPair {
+                seed,
+                a: s[0],
+                b: s[1],
+            })
+            .collect::<Vec<_>>();
+        print!("{}", pair_report(&pairs, false));
+    }
+}
+
+fn create_dir(path: &str) {
+    if let Err(e) = std::fs::create_dir_all(path) {
+        fail(&format!("cannot create {}: {}", path, e));
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1)
+}
+
+fn usage() -> ! {
+    let names = contest::all()
+        .iter()
+        .map(|c| c.name())
+        .collect::<Vec<_>>()
+        .join("|");
+    eprintln!(
+        "Usage: {} <{}> <gen|run|eval|vis|judge|compare> [args...]",
+        std::env::args().next().unwrap(),
+        names
+    );
+    std::process::exit(1)
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.len() < 2 {
+        usage();
+    }
+    let contest = contest::find(&args[0]).unwrap_or_else(|| usage());
+    let rest = Args::parse(&args[2..]).unwrap_or_else(|| usage());
+
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    let bin = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|p| p.to_path_buf()))
+        .unwrap_or_else(|| fail("cannot find the binary directory"));
+    if let Err(e) = std::env::set_current_dir(root.join(contest.name())) {
+        fail(&format!("{}: {}", contest.name(), e));
+    }
+    let driver = Driver { contest, root, bin };
+    driver.build();
+
+    let range = || rest.range().unwrap_or_else(|| usage());
+    let seed = || rest.seed().unwrap_or_else(|| usage());
+    let jobs = || {
+        rest.get("--jobs")
+            .unwrap_or("1")
+            .parse()
+            .unwrap_or_else(|_| usage())
+    };
+    match args[1].as_str() {
+        "gen" if rest.only(&[]) => driver.gen(range()),
+        "run" if rest.only(&["--jobs", "--params"]) => {
+            driver.run(range(), jobs(), rest.get("--params"))
+        }
+        "eval" if rest.only(&[]) => driver.eval(range()),
+        "vis" if rest.only(&[]) => driver.vis(seed()),
+        "judge" if rest.only(&["--params"]) => driver.judge(seed(), rest.get("--params")),
+        "compare" if rest.only(&["--jobs", "--params-a", "--params-b", "--solver-b"]) => {
+            let same = rest.get("--params-a") == rest.get("--params-b");
+            if same && rest.get("--solver-b").is_none() {
+                usage();
+            }
+            driver.compare(range(), jobs(), &rest)
+        }
+        _ => usage(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Option<Args> {
+        Args::parse(
+            &s.split_whitespace()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn parses_seeds_and_options() {
+        let a = args("3 7 --jobs 4 --params k=1").unwrap();
+        assert_eq!(a.range(), Some((3, 7)));
+        assert_eq!(a.seed(), None);
+        assert_eq!(a.get("--jobs"), Some("4"));
+        assert_eq!(a.get("--params"), Some("k=1"));
+        assert!(a.only(&["--jobs", "--params"]));
+        assert!(!a.only(&["--jobs"]));
+
+        let a = args("5").unwrap();
+        assert_eq!((a.range(), a.seed()), (None, Some(5)));
+        assert_eq!(args("").unwrap().range(), Some(DEFAULT_SEEDS));
+        assert_eq!(args("7 3").unwrap().range(), None);
+        assert!(args("--jobs").is_none());
+        assert!(args("x").is_none());
+    }
+
+    // プログラム、引数、パス、環境変数の値
+    fn fields(job: &Job) -> Vec<String> {
+        let mut fields = vec![job.program.clone()];
+        fields.extend(job.args.iter().cloned());
+        fields.extend(job.dir.iter().chain(&job.stdin).chain(&job.stdout).cloned());
+        fields.extend(job.env.iter().map(|(_, v)| v.clone()));
+        fields
+    }
+
+    #[test]
+    fn every_contest_has_runnable_jobs() {
+        let bin = Path::new("/bin");
+        for contest in contest::all() {
+            let name = contest.name();
+            assert_eq!(contest::find(name).unwrap().name(), name);
+
+            // run は入力 (seed かファイル) と出力先の両方を使う
+            let run = fields(&contest.run("{bin}/solver", "out/{case}.txt").expand(12));
+            assert!(run.iter().any(|f| f == "{bin}/solver"), "{:?}", run);
+            assert!(run.iter().any(|f| f == "out/0012.txt"), "{:?}", run);
+            let input = contest.input().map(|input| expand(input, 12));
+            assert!(
+                run.iter().any(|f| f == "12" || Some(f) == input.as_ref()),
+                "{:?}",
+                run
+            );
+
+            // 採点は run と同じ出力を読む
+            if let Some(eval) = contest.eval("out/{case}.txt") {
+                let eval = fields(&eval.expand(12));
+                assert!(eval.iter().any(|f| f == "../out/0012.txt"), "{:?}", eval);
+            }
+
+            for job in contest
+                .eval("tools/out/{case}.txt")
+                .into_iter()
+                .chain(contest.vis().map(|(job, _)| job))
+                .chain(Some(contest.judge()))
+            {
+                let job = contest::resolve(&job, bin).expand(5);
+                assert!(
+                    fields(&job).iter().all(|f| !f.contains('{')),
+                    "{}: {}",
+                    name,
+                    job
+                );
+            }
+        }
+        assert!(contest::find("ahc000").is_none());
+    }
+}
@@ -0,0 +1,1286 @@
+// ライブラリを使っているソルバーを提出用の1ファイルにまとめる
+// Usage: bundle <solver.rs> [-o OUT] [--lib NAME=DIR]... [--no-check]
+//   NAME=DIR : ソルバーが use するクレート名とその src
+//              (default: heuristic_core=<workspace>/heuristic-core/src と、ソルバーのクレートに lib.rs があればそれ)
+//   OUT      : 出力先 (default: 標準出力)
+//   使われているクレートを末尾に `pub mod NAME { .. }` として埋め込む。
+//   mod x; はファイルを読んで入れ子のまま展開し、テストと使われていない要素は落とす。
+//   使われているかは、名前をモジュールのパス (use・再公開・glob を含む) で解決して決める。
+//   最後にソルバーのクレートと同じ依存で cargo check する (--no-check で省略)
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Ident,
+    Punct,
+    Literal,
+    Lifetime,
+    Comment,
+    Space,
+}
+
+// src 上のバイト範囲
+#[derive(Clone, Copy, Debug)]
+struct Token {
+    kind: Kind,
+    start: usize,
+    end: usize,
+}
+
+// r"..", r#".."#, br".." の長さ
+fn raw_string_len(s: &str) -> Option<usize> {
+    let b = s.as_bytes();
+    let mut i = 0;
+    if b.get(i) == Some(&b'b') {
+        i += 1;
+    }
+    if b.get(i) != Some(&b'r') {
+        return None;
+    }
+    i += 1;
+    let hashes = b[i..].iter().take_while(|&&c| c == b'#').count();
+    i += hashes;
+    if b.get(i) != Some(&b'"') {
+        return None;
+    }
+    let close = format!("\"{}", "#".repeat(hashes));
+
+    s[i + 1..].find(&close).map(|p| i + 1 + p + close.len())
+}
+
+// 括弧の対応と識別子さえ取れればいいので、リテラルとコメントを正しく読み飛ばすことだけに気をつける
+fn tokenize(src: &str) -> Vec<Token> {
+    let b = src.as_bytes();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < b.len() {
+        let start = i;
+        let c = b[i];
+        let kind = if c.is_ascii_whitespace() {
+            while i < b.len() && b[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            Kind::Space
+        } else if src[i..].starts_with("//") {
+            while i < b.len() && b[i] != b'\n' {
+                i += 1;
+            }
+            Kind::Comment
+        } else if src[i..].starts_with("/*") {
+            let mut depth = 0;
+            // 中身は文字の途中で切れてもいいようにバイト列で見る
+            while i < b.len() {
+                if b[i..].starts_with(b"/*") {
+                    depth += 1;
+                    i += 2;
+                } else if b[i..].starts_with(b"*/") {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+            Kind::Comment
+        } else if let Some(len) = raw_string_len(&src[i..]) {
+            i += len;
+            Kind::Literal
+        } else if c == b'"' || (c == b'b' && b.get(i + 1) == Some(&b'"')) {
+            i += if c == b'b' { 2 } else { 1 };
+            while i < b.len() && b[i] != b'"' {
+                if b[i] == b'\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            i += 1;
+            Kind::Literal
+        } else if c == b'\'' || (c == b'b' && b.get(i + 1) == Some(&b'\'')) {
+            i += if c == b'b' { 2 } else { 1 };
+            if b.get(i) == Some(&b'\\') {
+                // エスケープされた文字
+                i += 2;
+                while i < b.len() && b[i] != b'\'' {
+                    i += 1;
+                }
+                i += 1;
+                Kind::Literal
+            } else {
+                let len = src[i..].chars().next().map_or(1, |c| c.len_utf8());
+                if b.get(i + len) == Some(&b'\'') {
+                    i += len + 1;
+                    Kind::Literal
+                } else {
+                    while i < b.len() && (b[i] == b'_' || b[i].is_ascii_alphanumeric()) {
+                        i += 1;
+                    }
+                    Kind::Lifetime
+                }
+            }
+        } else if c == b'_' || c.is_ascii_alphabetic() {
+            while i < b.len() && (b[i] == b'_' || b[i].is_ascii_alphanumeric()) {
+                i += 1;
+            }
+            Kind::Ident
+        } else if c.is_ascii_digit() {
+            while i < b.len()
+                && (b[i] == b'_'
+                    || b[i].is_ascii_alphanumeric()
+                    || (b[i] == b'.' && matches!(b.get(i + 1), Some(d) if d.is_ascii_digit())))
+            {
+                i += 1;
+            }
+            Kind::Literal
+        } else {
+            i += src[i..].chars().next().map_or(1, |c| c.len_utf8());
+            Kind::Punct
+        };
+        tokens.push(Token {
+            kind,
+            start,
+            end: i.min(b.len()),
+        });
+    }
+
+    tokens
+}
+
+struct File {
+    src: String,
+    tokens: Vec<Token>,
+}
+
+impl File {
+    fn new(src: String) -> Self {
+        let tokens = tokenize(&src);
+        Self { src, tokens }
+    }
+
+    fn text(&self, k: usize) -> &str {
+        &self.src[self.tokens[k].start..self.tokens[k].end]
+    }
+
+    fn is_trivia(&self, k: usize) -> bool {
+        matches!(self.tokens[k].kind, Kind::Space | Kind::Comment)
+    }
+
+    // k 以降で最初の空白・コメントでないトークン
+    fn skip_trivia(&self, mut k: usize, hi: usize) -> usize {
+        while k < hi && self.is_trivia(k) {
+            k += 1;
+        }
+        k
+    }
+
+    // 開き括弧 k に対応する閉じ括弧
+    fn matching(&self, k: usize, hi: usize) -> usize {
+        let mut depth = 0;
+        for l in k..hi {
+            if self.tokens[l].kind != Kind::Punct {
+                continue;
+            }
+            match self.text(l) {
+                "(" | "[" | "{" => depth += 1,
+                ")" | "]" | "}" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return l;
+                    }
+                }
+                _ => {}
+            }
+        }
+        hi - 1
+    }
+
+    fn idents(&self, lo: usize, hi: usize) -> impl Iterator<Item = &str> + '_ {
+        (lo..hi)
+            .filter(move |&k| self.tokens[k].kind == Kind::Ident)
+            .map(move |k| self.text(k))
+    }
+
+    // 範囲内のパス a::b::c を識別子の列で。:: でつながっていない識別子は長さ1のパス
+    fn paths(&self, lo: usize, hi: usize) -> Vec<Vec<String>> {
+        let mut res = vec![];
+        let mut k = lo;
+        while k < hi {
+            if self.tokens[k].kind != Kind::Ident {
+                k += 1;
+                continue;
+            }
+            let mut segs = vec![self.text(k).to_string()];
+            k += 1;
+            loop {
+                let a = self.skip_trivia(k, hi);
+                if a + 1 < hi && self.text(a) == ":" && self.text(a + 1) == ":" {
+                    let b = self.skip_trivia(a + 2, hi);
+                    if b < hi && self.tokens[b].kind == Kind::Ident {
+                        segs.push(self.text(b).to_string());
+                        k = b + 1;
+                        continue;
+                    }
+                }
+                break;
+            }
+            res.push(segs);
+        }
+
+        res
+    }
+}
+
+// use 文で入る (名前, パス) の一覧。x::* は名前を "*" にする
+fn use_tree(file: &File, lo: usize, hi: usize) -> Vec<(String, Vec<String>)> {
+    let toks = (lo..hi)
+        .filter(|&k| !file.is_trivia(k))
+        .map(|k| file.text(k))
+        .collect::<Vec<_>>();
+    let mut res = vec![];
+    if let Some(k) = toks.iter().position(|&t| t == "use") {
+        parse_use(&toks, k + 1, &mut vec![], &mut res);
+    }
+
+    res
+}
+
+// toks[k] から use の木を1つ読む => 読み終えた位置
+fn parse_use(
+    toks: &[&str],
+    mut k: usize,
+    prefix: &mut Vec<String>,
+    res: &mut Vec<(String, Vec<String>)>,
+) -> usize {
+    let depth = prefix.len();
+    while let Some(&t) = toks.get(k) {
+        match t {
+            ":" => k += 1,
+            "*" => {
+                res.push((String::from("*"), prefix.clone()));
+                k += 1;
+                break;
+            }
+            "{" => {
+                k += 1;
+                while k < toks.len() && toks[k] != "}" {
+                    if toks[k] == "," {
+                        k += 1;
+                    } else {
+                        k = parse_use(toks, k, prefix, res);
+                    }
+                }
+                k += 1;
+                break;
+            }
+            "," | "}" | ";" | "as" => {
+                let mut path = prefix.clone();
+                // a::{self, ..} の self は a 自身
+                if path.last().map(|s| s.as_str()) == Some("self") {
+                    path.pop();
+                }
+                let name = if t == "as" {
+                    k += 2;
+                    toks.get(k - 1).map(|s| s.to_string())
+                } else {
+                    path.last().cloned()
+                };
+                if let Some(name) = name {
+                    res.push((name, path));
+                }
+                break;
+            }
+            _ => {
+                prefix.push(t.to_string());
+                k += 1;
+            }
+        }
+    }
+    prefix.truncate(depth);
+
+    k
+}
+
+// ライブラリの要素を (モジュールのパス, 名前) で指す
+type Key = (Vec<String>, String);
+
+const LOOKUP_DEPTH: usize = 16; // use をたどる深さの上限 (循環していても止まるように)
+
+// モジュールから見える名前
+#[derive(Default)]
+struct Scope {
+    defined: HashSet<String>,              // このモジュールで定義した要素
+    children: HashSet<String>,             // 子モジュール
+    imports: HashMap<String, Vec<String>>, // use で入れた名前 => ライブラリの根からのパス
+    globs: Vec<Vec<String>>,               // use x::*; の x (根から)
+}
+
+// crate:: / self:: / super:: を外して、どのモジュールからたどるか
+fn anchor<'a>(m: &[String], segs: &'a [String]) -> Option<(Vec<String>, &'a [String])> {
+    match segs.first()?.as_str() {
+        "crate" => Some((vec![], &segs[1..])),
+        "self" => Some((m.to_vec(), &segs[1..])),
+        "super" => {
+            let mut cur = m.to_vec();
+            let mut k = 0;
+            while segs.get(k).map(|s| s.as_str()) == Some("super") {
+                cur.pop()?;
+                k += 1;
+            }
+            Some((cur, &segs[k..]))
+        }
+        _ => Some((m.to_vec(), segs)),
+    }
+}
+
+// ライブラリの中の名前解決 (モジュールのパス => Scope)
+struct Lib {
+    scopes: HashMap<Vec<String>, Scope>,
+}
+
+impl Lib {
+    // モジュール cur から segs をたどる。途中で通った use (再公開) を trail に入れる
+    fn lookup(
+        &self,
+        cur: &[String],
+        segs: &[String],
+        depth: usize,
+        trail: &mut Vec<Key>,
+    ) -> Option<Key> {
+        let scope = self.scopes.get(cur)?;
+        let (s, rest) = segs.split_first()?;
+        if depth > LOOKUP_DEPTH {
+            return None;
+        }
+        if scope.children.contains(s) {
+            let mut next = cur.to_vec();
+            next.push(s.clone());
+            return self.lookup(&next, rest, depth + 1, trail);
+        }
+        if scope.defined.contains(s) {
+            return Some((cur.to_vec(), s.clone()));
+        }
+        if let Some(path) = scope.imports.get(s) {
+            let key = self.lookup(&[], &[&path[..], rest].concat(), depth + 1, trail)?;
+            trail.push((cur.to_vec(), s.clone()));
+            return Some(key);
+        }
+        scope
+            .globs
+            .iter()
+            .find_map(|g| self.lookup(&[], &[&g[..], segs].concat(), depth + 1, trail))
+    }
+
+    // モジュール m の中に書かれたパス
+    fn resolve(&self, m: &[String], segs: &[String], trail: &mut Vec<Key>) -> Option<Key> {
+        let (cur, rest) = anchor(m, segs)?;
+        self.lookup(&cur, rest, 0, trail)
+    }
+}
+
+// 見つかった要素と、そこまでにたどった use を used に入れる
+fn add(used: &mut HashSet<Key>, found: impl FnOnce(&mut Vec<Key>) -> Option<Key>) {
+    let mut trail = vec![];
+    if let Some(key) = found(&mut trail) {
+        used.insert(key);
+        used.extend(trail);
+    }
+}
+
+// モジュールの中身の並びに出てくる要素 (前の空白・コメントを含む)
+struct Item {
+    file: usize,
+    lo: usize, // トークンの範囲
+    hi: usize,
+    kw: String,
+    name: Option<String>,
+    // impl の対象の型とトレイト
+    self_ty: Option<String>,
+    trait_name: Option<String>,
+    test: bool,
+    // mod の場合: 展開した中身と、中身を差し込む位置
+    children: Option<Vec<usize>>,
+    open: usize,  // mod x; なら ';'、 mod x { .. } なら '{'
+    close: usize, // mod x { .. } の '}'
+}
+
+struct Bundler<'a> {
+    files: Vec<File>,
+    items: Vec<Item>,
+    load: &'a dyn Fn(&Path) -> Option<String>,
+}
+
+const QUALIFIERS: [&str; 5] = ["pub", "unsafe", "async", "extern", "default"];
+const SEMICOLON_ONLY: [&str; 4] = ["const", "static", "type", "use"];
+
+impl<'a> Bundler<'a> {
+    fn new(load: &'a dyn Fn(&Path) -> Option<String>) -> Self {
+        Self {
+            files: vec![],
+            items: vec![],
+            load,
+        }
+    }
+
+    fn add_file(&mut self, path: &Path) -> Result<usize, String> {
+        let src = (self.load)(path).ok_or(format!("cannot read {}", path.display()))?;
+        self.files.push(File::new(src));
+
+        Ok(self.files.len() - 1)
+    }
+
+    // ファイル全体をモジュールとして読む (dir: mod x; の x を探すディレクトリ)
+    fn parse_file(&mut self, path: &Path, dir: &Path) -> Result<Vec<usize>, String> {
+        let f = self.add_file(path)?;
+        let hi = self.files[f].tokens.len();
+
+        self.parse_items(f, 0, hi, dir)
+    }
+
+    fn parse_items(
+        &mut self,
+        f: usize,
+        lo: usize,
+        hi: usize,
+        dir: &Path,
+    ) -> Result<Vec<usize>, String> {
+        let mut res = vec![];
+        let mut k = lo;
+        while k < hi {
+            let item_lo = k;
+            let file = &self.files[f];
+            k = file.skip_trivia(k, hi);
+            if k == hi {
+                break;
+            }
+
+            // 属性
+            let mut test = false;
+            let mut inner = false;
+            while k < hi && file.text(k) == "#" {
+                let mut l = file.skip_trivia(k + 1, hi);
+                if file.text(l) == "!" {
+                    inner = true;
+                    l = file.skip_trivia(l + 1, hi);
+                }
+                let close = file.matching(l, hi);
+                let attr = file.idents(l, close).collect::<Vec<_>>();
+                if attr == ["test"] || attr == ["cfg", "test"] {
+                    test = true;
+                }
+                k = file.skip_trivia(close + 1, hi);
+                if inner {
+                    break;
+                }
+            }
+            if inner {
+                res.push(self.push_item(f, item_lo, k, String::from("#!"), test));
+                continue;
+            }
+
+            // 修飾子を飛ばしてキーワードを探す
+            let mut l = k;
+            loop {
+                let t = file.text(l);
+                if QUALIFIERS.contains(&t) {
+                    l = file.skip_trivia(l + 1, hi);
+                    if t == "pub" && file.text(l) == "(" {
+                        l = file.skip_trivia(file.matching(l, hi) + 1, hi);
+                    }
+                    if t == "extern" && file.tokens[l].kind == Kind::Literal {
+                        l = file.skip_trivia(l + 1, hi);
+                    }
+                } else if t == "const" && {
+                    let m = file.skip_trivia(l + 1, hi);
+                    m < hi && (file.text(m) == "fn" || file.text(m) == "unsafe")
+                } {
+                    l = file.skip_trivia(l + 1, hi);
+                } else {
+                    break;
+                }
+            }
+            let kw = file.text(l).to_string();
+            let after_kw = file.skip_trivia(l + 1, hi);
+            let name = match kw.as_str() {
+                "fn" | "struct" | "enum" | "union" | "trait" | "mod" | "const" | "static"
+                | "type" => Some(file.text(after_kw).to_string()),
+                "macro_rules" => Some(file.text(file.skip_trivia(after_kw + 1, hi)).to_string()),
+                _ => None,
+            };
+
+            // 終わりを探す
+            let mut end = l;
+            let mut brace = None;
+            while end < hi {
+                if file.tokens[end].kind == Kind::Punct {
+                    match file.text(end) {
+                        ";" => break,
+                        "{" if !SEMICOLON_ONLY.contains(&kw.as_str()) => {
+                            brace = Some(end);
+                            end = file.matching(end, hi);
+                            break;
+                        }
+                        "(" | "[" | "{" => end = file.matching(end, hi),
+                        _ => {}
+                    }
+                }
+                end += 1;
+            }
+            let end = end.min(hi - 1);
+
+            let (self_ty, trait_name) = if kw == "impl" {
+                impl_types(file, after_kw, brace.unwrap_or(end))
+            } else {
+                (None, None)
+            };
+
+            let idx = self.push_item(f, item_lo, end + 1, kw.clone(), test);
+            self.items[idx].name = name.clone();
+            self.items[idx].self_ty = self_ty;
+            self.items[idx].trait_name = trait_name;
+            if kw == "mod" && !test {
+                let name = name.unwrap();
+                let sub_dir = dir.join(&name);
+                let children = match brace {
+                    Some(open) => {
+                        self.items[idx].open = open;
+                        self.items[idx].close = end;
+                        self.parse_items(f, open + 1, end, &sub_dir)?
+                    }
+                    None => {
+                        self.items[idx].open = end;
+                        let path = dir.join(format!("{}.rs", name));
+                        if (self.load)(&path).is_some() {
+                            self.parse_file(&path, &sub_dir)?
+                        } else {
+                            self.parse_file(&sub_dir.join("mod.rs"), &sub_dir)?
+                        }
+                    }
+                };
+                self.items[idx].children = Some(children);
+            }
+            res.push(idx);
+            k = end + 1;
+        }
+
+        Ok(res)
+    }
+
+    fn push_item(&mut self, file: usize, lo: usize, hi: usize, kw: String, test: bool) -> usize {
+        self.items.push(Item {
+            file,
+            lo,
+            hi,
+            kw,
+            name: None,
+            self_ty: None,
+            trait_name: None,
+            test,
+            children: None,
+            open: 0,
+            close: 0,
+        });
+
+        self.items.len() - 1
+    }
+
+    // モジュール以外の要素を (番号, 親のモジュール, モジュールのパス) で列挙する
+    fn all_items(
+        &self,
+        roots: &[usize],
+        parent: Option<usize>,
+        path: &[String],
+        res: &mut Vec<(usize, Option<usize>, Vec<String>)>,
+    ) {
+        for &i in roots {
+            let item = &self.items[i];
+            if item.test {
+                continue;
+            }
+            match (&item.children, &item.name) {
+                (Some(children), Some(name)) => {
+                    let mut sub = path.to_vec();
+                    sub.push(name.clone());
+                    self.all_items(children, Some(i), &sub, res);
+                }
+                _ => res.push((i, parent, path.to_vec())),
+            }
+        }
+    }
+
+    // モジュールごとに、定義した名前・子モジュール・use で入れた名前をまとめる
+    fn lib(&self, roots: &[usize]) -> Lib {
+        let mut items = vec![];
+        self.all_items(roots, None, &[], &mut items);
+        let mut scopes = HashMap::new();
+        scopes.insert(vec![], Scope::default());
+        for (i, _, path) in items {
+            let item = &self.items[i];
+            // 親のモジュールを子として登録する
+            for d in 0..path.len() {
+                let scope: &mut Scope = scopes.entry(path[..d].to_vec()).or_default();
+                scope.children.insert(path[d].clone());
+            }
+            let scope = scopes.entry(path.clone()).or_default();
+            if let Some(name) = &item.name {
+                scope.defined.insert(name.clone());
+            }
+            if item.kw != "use" {
+                continue;
+            }
+            for (name, target) in use_tree(&self.files[item.file], item.lo, item.hi) {
+                let (cur, rest) = match anchor(&path, &target) {
+                    Some(e) => e,
+                    None => continue,
+                };
+                let target = [&cur[..], rest].concat();
+                if name == "*" {
+                    scope.globs.push(target);
+                } else {
+                    scope.imports.insert(name, target);
+                }
+            }
+        }
+
+        Lib { scopes }
+    }
+
+    // used の要素から辿れる要素を残す
+    fn mark(&self, roots: &[usize], lib: &Lib, mut used: HashSet<Key>) -> Vec<bool> {
+        let mut items = vec![];
+        self.all_items(roots, None, &[], &mut items);
+
+        let mut kept = vec![false; self.items.len()];
+        let mut visited = vec![false; self.items.len()];
+        loop {
+            let mut changed = false;
+            for (i, parent, path) in &items {
+                let i = *i;
+                if visited[i] {
+                    continue;
+                }
+                let item = &self.items[i];
+                let file = &self.files[item.file];
+                let keep = match (item.kw.as_str(), &item.name) {
+                    // 使っていなくても消すと名前解決が壊れうるので、モジュールを残すなら残す。
+                    // 再公開した名前が使われているなら、それだけでモジュールごと残す
+                    ("use", _) => {
+                        let reexported = use_tree(file, item.lo, item.hi)
+                            .into_iter()
+                            .any(|(name, _)| used.contains(&(path.clone(), name)));
+                        if reexported {
+                            kept[i] = true;
+                        }
+                        reexported
+                            || match parent {
+                                Some(p) => self.has_kept(*p, &kept),
+                                None => true,
+                            }
+                    }
+                    ("impl", _) => {
+                        let resolve = |name: &Option<String>| {
+                            let name = name.clone()?;
+                            lib.resolve(path, &[name], &mut vec![])
+                        };
+                        match (resolve(&item.self_ty), resolve(&item.trait_name)) {
+                            (Some(ty), _) => used.contains(&ty),
+                            (None, Some(tr)) => used.contains(&tr),
+                            (None, None) => true,
+                        }
+                    }
+                    (_, Some(name)) => used.contains(&(path.clone(), name.clone())),
+                    _ => true,
+                };
+                if !keep {
+                    continue;
+                }
+                visited[i] = true;
+                changed = true;
+                if item.kw == "use" {
+                    // use の先が消えると解決できないので残す
+                    for (name, target) in use_tree(file, item.lo, item.hi) {
+                        if name != "*" {
+                            add(&mut used, |trail| lib.resolve(path, &target, trail));
+                        }
+                    }
+                } else {
+                    kept[i] = true;
+                    for segs in file.paths(item.lo, item.hi) {
+                        add(&mut used, |trail| lib.resolve(path, &segs, trail));
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        kept
+    }
+
+    // 書き出すか。use はモジュールを書き出すときに一緒に書き出すので、それだけでは数えない
+    // (再公開した名前が使われている use は mark で kept にしてある)
+    fn has_kept(&self, i: usize, kept: &[bool]) -> bool {
+        match &self.items[i].children {
+            Some(children) => children.iter().any(|&c| {
+                !self.items[c].test && self.items[c].kw != "#!" && self.has_kept(c, kept)
+            }),
+            None => kept[i],
+        }
+    }
+
+    // crate:: を crate::NAME:: に置き換えながら書き出す
+    fn emit(&self, f: usize, lo: usize, hi: usize, krate: Option<&str>, out: &mut String) {
+        let file = &self.files[f];
+        for k in lo..hi {
+            out.push_str(file.text(k));
+            if let Some(krate) = krate {
+                if file.text(k) == "crate"
+                    && k + 2 < file.tokens.len()
+                    && file.text(k + 1) == ":"
+                    && file.text(k + 2) == ":"
+                {
+                    out.push_str("::");
+                    out.push_str(krate);
+                }
+            }
+        }
+    }
+
+    fn render(&self, roots: &[usize], kept: &[bool], krate: Option<&str>, out: &mut String) {
+        for &i in roots {
+            let item = &self.items[i];
+            let with_module = item.kw == "use" || item.kw == "#!";
+            if item.test || (!with_module && !self.has_kept(i, kept)) {
+                continue;
+            }
+            match &item.children {
+                Some(children) if item.close == 0 => {
+                    // mod x; => mod x { .. }
+                    self.emit(item.file, item.lo, item.open, krate, out);
+                    out.push_str(" {\n");
+                    self.render(children, kept, krate, out);
+                    out.push('}');
+                }
+                Some(children) => {
+                    self.emit(item.file, item.lo, item.open + 1, krate, out);
+                    self.render(children, kept, krate, out);
+                    self.emit(item.file, item.close, item.hi, krate, out);
+                }
+                None => self.emit(item.file, item.lo, item.hi, krate, out),
+            }
+        }
+        out.push('\n');
+    }
+}
+
+// impl<..> Trait for Type { の Type と Trait (ジェネリクスの中は見ない)
+fn impl_types(file: &File, lo: usize, hi: usize) -> (Option<String>, Option<String>) {
+    let mut depth = 0;
+    let mut first = None;
+    let mut after_for = None;
+    let mut seen_for = false;
+    for k in lo..hi {
+        let t = file.text(k);
+        match t {
+            "<" => depth += 1,
+            ">" if k > 0 && file.text(k - 1) != "-" => depth -= 1,
+            "where" if depth == 0 => break,
+            "for" if depth == 0 => seen_for = true,
+            _ if depth == 0 && file.tokens[k].kind == Kind::Ident => {
+                if t == "dyn" || t == "mut" {
+                    continue;
+                }
+                if seen_for {
+                    if after_for.is_none() {
+                        after_for = Some(t.to_string());
+                    }
+                } else if first.is_none() {
+                    first = Some(t.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if seen_for {
+        (after_for, first)
+    } else {
+        (first, None)
+    }
+}
+
+// ソルバーに書かれたパスをクレート krate の中で解決する
+// krate:: で始まるか、use krate::.. で入れた名前 (別名・glob を含む) から始まるものだけを見る
+fn solver_lookup(
+    lib: &Lib,
+    imports: &HashMap<String, Vec<String>>,
+    globs: &[Vec<String>],
+    krate: &str,
+    segs: &[String],
+    trail: &mut Vec<Key>,
+) -> Option<Key> {
+    if segs[0] == krate {
+        return lib.lookup(&[], &segs[1..], 0, trail);
+    }
+    if let Some(path) = imports.get(&segs[0]) {
+        return lib.lookup(&[], &[&path[..], &segs[1..]].concat(), 0, trail);
+    }
+    globs
+        .iter()
+        .find_map(|g| lib.lookup(&[], &[&g[..], segs].concat(), 0, trail))
+}
+
+// ソルバーから使われているクレートを埋め込んだソースを作る
+// libs: (クレート名, src ディレクトリ)
+fn bundle(
+    solver: &Path,
+    libs: &[(String, PathBuf)],
+    load: &dyn Fn(&Path) -> Option<String>,
+) -> Result<String, String> {
+    let mut b = Bundler::new(load);
+    let dir = solver.parent().unwrap_or(Path::new("."));
+    let roots = b.parse_file(solver, dir)?;
+
+    let mut items = vec![];
+    b.all_items(&roots, None, &[], &mut items);
+    let items = items.into_iter().map(|(i, _, _)| i).collect::<Vec<_>>();
+
+    let mut out = String::new();
+    let solver_kept = vec![true; b.items.len()];
+    b.render(&roots, &solver_kept, None, &mut out);
+
+    for (name, dir) in libs {
+        let mentioned = items.iter().any(|&i| {
+            let item = &b.items[i];
+            b.files[item.file]
+                .idents(item.lo, item.hi)
+                .any(|s| s == name)
+        });
+        if !mentioned {
+            continue;
+        }
+
+        // ソルバーの use krate::.. で入れた名前
+        let mut imports = HashMap::new();
+        let mut globs = vec![];
+        for &i in &items {
+            let item = &b.items[i];
+            if item.kw != "use" {
+                continue;
+            }
+            for (alias, target) in use_tree(&b.files[item.file], item.lo, item.hi) {
+                if target.first() != Some(name) {
+                    continue;
+                }
+                if alias == "*" {
+                    globs.push(target[1..].to_vec());
+                } else {
+                    imports.insert(alias, target[1..].to_vec());
+                }
+            }
+        }
+
+        // ソルバーのパスが指すライブラリの要素から辿る (名前だけでなくモジュールのパスで区別する)
+        let lib_roots = b.parse_file(&dir.join("lib.rs"), dir)?;
+        let lib = b.lib(&lib_roots);
+        let mut used = HashSet::new();
+        for &i in &items {
+            let item = &b.items[i];
+            let file = &b.files[item.file];
+            let paths = if item.kw == "use" {
+                use_tree(file, item.lo, item.hi)
+                    .into_iter()
+                    .filter(|(alias, _)| alias != "*")
+                    .map(|(_, target)| target)
+                    .collect()
+            } else {
+                file.paths(item.lo, item.hi)
+            };
+            for segs in paths {
+                add(&mut used, |trail| {
+                    solver_lookup(&lib, &imports, &globs, name, &segs, trail)
+                });
+            }
+        }
+        if used.is_empty() {
+            continue;
+        }
+
+        let kept = b.mark(&lib_roots, &lib, used);
+        out.push_str(&format!(
+            "\n// ---- 以下は bundle で埋め込んだ {} ----\n#[allow(dead_code, unused_imports)]\npub mod {} {{\n",
+            name, name
+        ));
+        let mut body = String::new();
+        b.render(&lib_roots, &kept, Some(name), &mut body);
+        out.push_str(body.trim_matches('\n'));
+        out.push_str("\n}\n");
+    }
+
+    Ok(out)
+}
+
+// ソルバーのクレートに lib.rs があれば (クレート名, src ディレクトリ)
+fn solver_lib(solver: &Path) -> Option<(String, PathBuf)> {
+    let manifest = solver
+        .ancestors()
+        .map(|d| d.join("Cargo.toml"))
+        .find(|p| p.exists())?;
+    let src = manifest.parent()?.join("src");
+    if !src.join("lib.rs").exists() {
+        return None;
+    }
+    let toml = std::fs::read_to_string(&manifest).ok()?;
+    let name = toml
+        .lines()
+        .map(|line| line.trim())
+        .find(|line| line.starts_with("name"))?
+        .split('"')
+        .nth(1)?;
+
+    Some((name.replace('-', "_"), src))
+}
+
+// ソルバーのクレートと同じ依存 (path 依存は除く) の一時クレートで cargo check する
+fn check(bundled: &str, solver: &Path, workspace: &Path) -> Result<(), String> {
+    let manifest = solver
+        .ancestors()
+        .map(|d| d.join("Cargo.toml"))
+        .find(|p| p.exists())
+        .ok_or("Cargo.toml not found")?;
+    let toml = std::fs::read_to_string(&manifest).map_err(|e| e.to_string())?;
+
+    let mut package = String::from("bundle");
+    let mut edition = String::from("2018");
+    let mut deps = vec![];
+    let mut section = String::new();
+    for line in toml.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            section = line.to_string();
+            continue;
+        }
+        if section == "[package]" && line.starts_with("name") {
+            package = line.split('"').nth(1).unwrap_or("bundle").to_string();
+        }
+        if section == "[package]" && line.starts_with("edition") {
+            edition = line.split('"').nth(1).unwrap_or("2018").to_string();
+        }
+        if section == "[dependencies]"
+            && !line.is_empty()
+            && !line.starts_with('#')
+            && !line.contains("path")
+        {
+            deps.push(line.to_string());
+        }
+    }
+
+    let dir = workspace.join("target").join("bundle").join(&package);
+    std::fs::create_dir_all(dir.join("src")).map_err(|e| e.to_string())?;
+    std::fs::write(
+        dir.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"{}-bundle\"\nversion = \"0.1.0\"\nedition = \"{}\"\n\n[dependencies]\n{}\n\n[workspace]\n",
+            package,
+            edition,
+            deps.join("\n")
+        ),
+    )
+    .map_err(|e| e.to_string())?;
+    std::fs::write(dir.join("src").join("main.rs"), bundled).map_err(|e| e.to_string())?;
+
+    let mut cmd = Command::new(std::env::var("CARGO").unwrap_or_else(|_| String::from("cargo")));
+    cmd.arg("check").arg("--quiet").current_dir(&dir);
+    if std::env::var("CARGO_TARGET_DIR").is_err() {
+        cmd.env("CARGO_TARGET_DIR", workspace.join("target").join("bundle"));
+    }
+    let status = cmd.status().map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("cargo check failed: {}", dir.display()))
+    }
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: {} <solver.rs> [-o OUT] [--lib NAME=DIR]... [--no-check]",
+        std::env::args().next().unwrap()
+    );
+    std::process::exit(1)
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.is_empty() {
+        usage();
+    }
+    let workspace = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .to_path_buf();
+
+    let solver = PathBuf::from(&args[0]);
+    let mut out_path = None;
+    let mut libs = vec![];
+    let mut no_check = false;
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "-o" => {
+                out_path = Some(args.get(i + 1).unwrap_or_else(|| usage()).clone());
+                i += 1;
+            }
+            "--lib" => {
+                let s = args.get(i + 1).unwrap_or_else(|| usage());
+                let mut kv = s.splitn(2, '=');
+                let name = kv.next().unwrap().to_string();
+                let dir = PathBuf::from(kv.next().unwrap_or_else(|| usage()));
+                libs.push((name, dir));
+                i += 1;
+            }
+            "--no-check" => no_check = true,
+            _ => usage(),
+        }
+        i += 1;
+    }
+    if libs.is_empty() {
+        libs.push((
+            String::from("heuristic_core"),
+            workspace.join("heuristic-core").join("src"),
+        ));
+        libs.extend(solver_lib(&solver));
+    }
+
+    let load = |path: &Path| std::fs::read_to_string(path).ok();
+    let bundled = bundle(&solver, &libs, &load).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    });
+
+    match &out_path {
+        Some(path) => std::fs::write(path, &bundled).unwrap(),
+        None => print!("{}", bundled),
+    }
+
+    if !no_check {
+        if let Err(e) = check(&bundled, &solver, &workspace) {
+            eprintln!("{}", e);
+            std::process::exit(1)
+        }
+        eprintln!("check: ok");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn run(solver: &str, lib: &[(&str, &str)]) -> String {
+        let mut files = HashMap::new();
+        files.insert(PathBuf::from("bin/a.rs"), solver.to_string());
+        for (path, src) in lib {
+            files.insert(PathBuf::from("lib").join(path), src.to_string());
+        }
+        let load = |path: &Path| files.get(path).cloned();
+        let libs = vec![(String::from("mylib"), PathBuf::from("lib"))];
+
+        bundle(Path::new("bin/a.rs"), &libs, &load).unwrap()
+    }
+
+    #[test]
+    fn inlines_nested_modules_and_drops_unused_items() {
+        let out = run(
+            "use mylib::geom::Coord;\nfn main() { let _ = Coord::new(); }\n",
+            &[
+                ("lib.rs", "pub mod geom;\npub mod rng;\n"),
+                (
+                    "geom.rs",
+                    "pub mod inner;\npub struct Coord;\nimpl Coord { pub fn new() -> Self { crate::geom::inner::zero(); Coord } }\npub fn unused() {}\n",
+                ),
+                ("geom/inner.rs", "pub fn zero() {}\npub fn one() {}\n"),
+                ("rng.rs", "pub struct Rng;\n"),
+            ],
+        );
+
+        assert!(out.contains("pub mod mylib {"));
+        assert!(out.contains("pub mod geom {"));
+        assert!(out.contains("pub mod inner {"));
+        assert!(out.contains("crate::mylib::geom::inner::zero()"));
+        assert!(out.contains("pub struct Coord;"));
+        assert!(!out.contains("fn unused"));
+        assert!(!out.contains("fn one"));
+        assert!(!out.contains("mod rng"));
+    }
+
+    #[test]
+    fn strips_tests_from_solver_and_library() {
+        let out = run(
+            "use mylib::f;\nfn main() { f(); }\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn t() {}\n}\n",
+            &[(
+                "lib.rs",
+                "pub fn f() {}\n#[test]\nfn g() { f(); }\n#[cfg(test)]\nmod tests { use super::*; }\n",
+            )],
+        );
+
+        assert!(out.contains("fn main()"));
+        assert!(out.contains("pub fn f() {}"));
+        assert!(!out.contains("tests"));
+        assert!(!out.contains("fn g"));
+    }
+
+    #[test]
+    fn keeps_impls_of_kept_types_only() {
+        let out = run(
+            "use mylib::A;\nfn main() { let _ = A::default(); }\n",
+            &[(
+                "lib.rs",
+                "pub struct A;\npub struct B;\nimpl Default for A { fn default() -> Self { A } }\nimpl<T: Fn() -> u8> From<T> for B { fn from(_: T) -> Self { B } }\n",
+            )],
+        );
+
+        assert!(out.contains("impl Default for A"));
+        assert!(!out.contains("for B"));
+    }
+
+    #[test]
+    fn tokenizer_skips_literals_and_comments() {
+        let src = "let s = \"{ // }\"; let c = '{'; let r = r#\"}\"#; /* { */ 'a: loop {}";
+        let f = File::new(src.to_string());
+        let braces = (0..f.tokens.len())
+            .filter(|&k| f.tokens[k].kind == Kind::Punct && (f.text(k) == "{" || f.text(k) == "}"))
+            .count();
+        assert_eq!(braces, 2);
+        assert!(f.tokens.iter().any(|t| t.kind == Kind::Lifetime));
+    }
+
+    #[test]
+    fn block_comments_may_contain_multibyte_text() {
+        let f = File::new(String::from("/* 変更処理 /* 入れ子 */ */ fn f() {}"));
+        assert_eq!(f.tokens[0].kind, Kind::Comment);
+        assert!((0..f.tokens.len()).any(|k| f.text(k) == "f"));
+    }
+
+    #[test]
+    fn follows_only_paths_into_the_library() {
+        let out = run(
+            "use mylib::{a::A, b};\nuse rand::Rng;\nfn main() { b::f(); }\n",
+            &[
+                ("lib.rs", "pub mod a;\npub mod b;\npub mod rng;\n"),
+                ("a.rs", "pub struct A;\npub struct Unused;\n"),
+                ("b.rs", "pub fn f() {}\n"),
+                ("rng.rs", "pub struct Rng;\n"),
+            ],
+        );
+
+        assert!(out.contains("pub struct A;"));
+        assert!(out.contains("pub fn f() {}"));
+        assert!(!out.contains("Unused"));
+        assert!(!out.contains("pub struct Rng"));
+    }
+
+    #[test]
+    fn imports_of_dropped_modules_do_not_keep_items() {
+        let out = run(
+            "use mylib::a::A;\nfn main() {}\n",
+            &[
+                ("lib.rs", "pub mod a;\npub mod b;\npub mod c;\n"),
+                ("a.rs", "pub struct A;\n"),
+                ("b.rs", "use crate::c::C;\npub struct B(C);\n"),
+                ("c.rs", "pub struct C;\n"),
+            ],
+        );
+
+        assert!(out.contains("pub struct A;"));
+        assert!(!out.contains("mod b"));
+        assert!(!out.contains("mod c"));
+    }
+
+    #[test]
+    fn same_names_in_other_modules_are_not_kept() {
+        let out = run(
+            "use mylib::b::run;\nfn main() { run(); }\n",
+            &[
+                ("lib.rs", "pub mod a;\npub mod b;\npub mod c;\n"),
+                ("a.rs", "pub fn run() {}\npub fn helper() {}\n"),
+                (
+                    "b.rs",
+                    "pub fn run() { crate::a::helper(); is_better(); }\nfn is_better() {}\n",
+                ),
+                ("c.rs", "pub fn is_better() {}\n"),
+            ],
+        );
+
+        assert!(out.contains("pub fn run() { crate::mylib::a::helper()"));
+        assert!(out.contains("pub fn helper() {}"));
+        assert!(out.contains("fn is_better() {}"));
+        assert!(!out.contains("pub fn run() {}"));
+        assert!(!out.contains("mod c"));
+    }
+
+    #[test]
+    fn reexports_keep_the_use_and_its_target() {
+        let out = run(
+            "use mylib::m::Budget;\nfn main() { let _ = Budget; }\n",
+            &[
+                ("lib.rs", "pub mod m;\npub mod t;\n"),
+                ("m.rs", "pub use crate::t::Budget;\npub struct Unused;\n"),
+                ("t.rs", "pub struct Budget;\n"),
+            ],
+        );
+
+        assert!(out.contains("pub use crate::mylib::t::Budget;"));
+        assert!(out.contains("pub struct Budget;"));
+        assert!(!out.contains("Unused"));
+    }
+
+    #[test]
+    fn ahc015_bundle_leaves_out_local_tools() {
+        let workspace = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+        let solver = workspace
+            .join("ahc015")
+            .join("src")
+            .join("bin")
+            .join("a.rs");
+        let mut libs = vec![(
+            String::from("heuristic_core"),
+            workspace.join("heuristic-core").join("src"),
+        )];
+        libs.extend(solver_lib(&solver));
+        let load = |path: &Path| std::fs::read_to_string(path).ok();
+        let out = bundle(&solver, &libs, &load).unwrap();
+
+        assert!(out.contains("pub mod game {"));
+        assert!(out.contains("pub struct IO"));
+        assert!(!out.contains("mod harness"));
+        assert!(!out.contains("mod ledger"));
+        assert!(!out.contains("pub fn expand("));
+    }
+
+    #[test]
+    fn glob_imports_resolve_in_their_module() {
+        let out = run(
+            "use mylib::a::*;\nfn main() { f(); }\n",
+            &[
+                ("lib.rs", "pub mod a;\npub mod b;\n"),
+                ("a.rs", "pub fn f() {}\npub fn g() {}\n"),
+                ("b.rs", "pub fn f() {}\n"),
+            ],
+        );
+
+        assert!(out.contains("pub mod a {"));
+        assert!(!out.contains("fn g"));
+        assert!(!out.contains("mod b"));
+    }
+
+    #[test]
+    fn skips_unused_crates() {
+        let out = run("fn main() {}\n", &[("lib.rs", "pub fn f() {}\n")]);
+        assert!(!out.contains("mylib"));
+    }
+}
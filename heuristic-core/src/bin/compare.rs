@@ -0,0 +1,257 @@
+// 2つの解法 (別々のバイナリ、または同じバイナリの2つのパラメータ) を同じ seed で動かして比べる
+// Usage: compare [--seeds FROM TO] [--score PREFIX] [--minimize] [--jobs J] [--eval EVAL]
+//                [--params-a P] [--params-b P] -- <command_a> [-- <command_b>]
+//   command : seed ごとに sh -c で実行する ({seed}, {case} の置き換えとスコアの読み方は tune と同じ)
+//             command_b を省くと A と同じコマンドを使う (このときは --params-a / --params-b で差をつける)
+//             {side} は a / b に置き換える (同じコマンドでも出力先を分けられる)
+//   EVAL    : command のあとに sh -c で実行する採点コマンド ({seed}, {case}, {side} を置き換える)。
+//             与えるとスコアは command ではなくこちらの出力から読む。ソルバーの自己申告を信用しないときに使う
+//   P       : 環境変数 PARAMS として渡す "name=value,..." (省くと呼び出し元の環境のまま)
+//   seed ごとのスコアと差 (B が良くなった向きを正) を並べ、勝ち負け、平均の相対改善、対応のある t 検定を表示する
+//   スコアが読めなかった seed は比較から外す
+//
+// 例 (ahc012 のディレクトリで):
+//   ../target/release/compare --seeds 0 49 --jobs 4 --params-b k=50 \
+//     -- '../target/release/ahc012 < tools/in/{case}.txt > /dev/null'
+// 例 (公式の vis で採点して):
+//   ../target/release/compare --seeds 0 49 --params-b k=50 \
+//     --eval 'cd tools && cargo run -q --release --bin vis in/{case}.txt out/{side}/{case}.txt' \
+//     -- '../target/release/ahc012 < tools/in/{case}.txt > tools/out/{side}/{case}.txt'
+// 例 (ビルドを2つ並べて):
+//   compare --seeds 0 49 -- './old < tools/in/{case}.txt' -- './new < tools/in/{case}.txt'
+
+use heuristic_core::harness::{par_map, parse_score, run};
+use heuristic_core::stats::{pair_report, SeedPair};
+
+struct Side {
+    name: &'static str, // {side} の置き換え先
+    command: String,
+    params: Option<String>,
+}
+
+struct Config {
+    a: Side,
+    b: Side,
+    seeds: Vec<u64>,
+    prefix: Option<String>,
+    minimize: bool,
+    jobs: usize,
+    eval: Option<String>,
+}
+
+fn evaluate(config: &Config) -> Vec<SeedPair> {
+    let mut tasks = vec![];
+    for &seed in &config.seeds {
+        for side in &[&config.a, &config.b] {
+            let command = side.command.replace("{side}", side.name);
+            let eval = config.eval.as_ref().map(|e| e.replace("{side}", side.name));
+            tasks.push((command, eval, side.params.clone(), seed));
+        }
+    }
+
+    let prefix = config.prefix.clone();
+    let scores = par_map(tasks, config.jobs, move |(command, eval, params, seed)| {
+        let out = run(&command, seed, params.as_deref())?;
+        let out = match eval {
+            Some(eval) => run(&eval, seed, None)?,
+            None => out,
+        };
+        parse_score(&out, prefix.as_deref())
+    });
+
+    config
+        .seeds
+        .iter()
+        .zip(scores.chunks(2))
+        .map(|(&seed, s)| SeedPair {
+            seed,
+            a: s[0],
+            b: s[1],
+        })
+        .collect()
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: {} [--seeds FROM TO] [--score PREFIX] [--minimize] [--jobs J] [--eval EVAL] [--params-a P] [--params-b P] -- <command_a> [-- <command_b>]",
+        std::env::args().next().unwrap()
+    );
+    std::process::exit(1)
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let mut seeds = (0..10).collect::<Vec<u64>>();
+    let mut prefix = None;
+    let mut minimize = false;
+    let mut jobs = 1;
+    let mut eval = None;
+    let mut params_a = None;
+    let mut params_b = None;
+    let mut commands = vec![];
+
+    let mut i = 0;
+    let arg = |i: usize| args.get(i).cloned().unwrap_or_else(|| usage());
+    while i < args.len() {
+        match args[i].as_str() {
+            "--seeds" => {
+                let from: u64 = arg(i + 1).parse().unwrap_or_else(|_| usage());
+                let to: u64 = arg(i + 2).parse().unwrap_or_else(|_| usage());
+                seeds = (from..=to).collect();
+                i += 2;
+            }
+            "--score" => {
+                prefix = Some(arg(i + 1));
+                i += 1;
+            }
+            "--minimize" => minimize = true,
+            "--jobs" => {
+                jobs = arg(i + 1).parse().unwrap_or_else(|_| usage());
+                i += 1;
+            }
+            "--eval" => {
+                eval = Some(arg(i + 1));
+                i += 1;
+            }
+            "--params-a" => {
+                params_a = Some(arg(i + 1));
+                i += 1;
+            }
+            "--params-b" => {
+                params_b = Some(arg(i + 1));
+                i += 1;
+            }
+            "--" => {
+                commands = args[i + 1..]
+                    .split(|a| a == "--")
+                    .map(|c| c.join(" "))
+                    .collect();
+                break;
+            }
+            _ => usage(),
+        }
+        i += 1;
+    }
+    let (command_a, command_b) = match commands.as_slice() {
+        [a] if !a.is_empty() && params_a != params_b => (a.clone(), a.clone()),
+        [a, b] if !a.is_empty() && !b.is_empty() => (a.clone(), b.clone()),
+        _ => usage(),
+    };
+    if seeds.is_empty() {
+        usage();
+    }
+
+    let config = Config {
+        a: Side {
+            name: "a",
+            command: command_a,
+            params: params_a,
+        },
+        b: Side {
+            name: "b",
+            command: command_b,
+            params: params_b,
+        },
+        seeds,
+        prefix,
+        minimize,
+        jobs,
+        eval,
+    };
+    let rows = evaluate(&config);
+    print!("{}", pair_report(&rows, config.minimize));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use heuristic_core::stats::Paired;
+
+    fn config(a: Side, b: Side, minimize: bool) -> Config {
+        Config {
+            a,
+            b,
+            seeds: (0..8).collect(),
+            prefix: None,
+            minimize,
+            jobs: 3,
+            eval: None,
+        }
+    }
+
+    #[test]
+    fn same_command_with_two_params() {
+        // PARAMS="k=..." の k だけスコアが上がる
+        let command = "echo \"score: $(( {seed} * 10 + ${PARAMS#k=} ))\"";
+        let side = |name: &'static str, k: &str| Side {
+            name,
+            command: command.to_string(),
+            params: Some(format!("k={}", k)),
+        };
+        let rows = evaluate(&config(side("a", "0"), side("b", "2"), false));
+        assert_eq!(
+            rows.iter().map(|r| r.seed).collect::<Vec<_>>(),
+            (0..8).collect::<Vec<_>>()
+        );
+        assert_eq!(rows[3].a, Some(30.0));
+        assert_eq!(rows[3].b, Some(32.0));
+
+        let p = Paired::from_pairs(&rows, false);
+        assert_eq!((p.wins, p.losses, p.ties), (8, 0, 0));
+        assert_eq!(p.mean_delta, 2.0);
+        assert_eq!(p.p_value, 0.0);
+
+        // 小さい方が良いなら B の負け
+        let p = Paired::from_pairs(&rows, true);
+        assert_eq!((p.wins, p.losses, p.ties), (0, 8, 0));
+    }
+
+    #[test]
+    fn seeds_without_score_are_excluded() {
+        let a = Side {
+            name: "a",
+            command: "echo score: {seed}".to_string(),
+            params: None,
+        };
+        // 奇数の seed では何も出さない
+        let b = Side {
+            name: "b",
+            command: "[ $(( {seed} % 2 )) -eq 0 ] && echo score: {seed}".to_string(),
+            params: None,
+        };
+        let rows = evaluate(&config(a, b, false));
+        assert_eq!(rows[1].b, None);
+        assert_eq!(rows[2].b, Some(2.0));
+
+        let p = Paired::from_pairs(&rows, false);
+        assert_eq!(p.deltas.len(), 4);
+        assert_eq!(p.ties, 4);
+    }
+
+    #[test]
+    fn eval_overrides_the_self_reported_score() {
+        let dir = std::env::temp_dir().join(format!("compare-eval-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("a")).unwrap();
+        std::fs::create_dir_all(dir.join("b")).unwrap();
+        let dir = dir.display().to_string();
+
+        // ソルバーは高いスコアを自己申告するが、採点は出力の行数を数える
+        let side = |name: &'static str, lines: usize| Side {
+            name,
+            command: format!(
+                "seq {} > {}/{{side}}/{{case}}.txt; echo score: 1000",
+                lines, dir
+            ),
+            params: None,
+        };
+        let mut config = config(side("a", 3), side("b", 5), false);
+        config.eval = Some(format!(
+            "echo score: $(wc -l < {}/{{side}}/{{case}}.txt)",
+            dir
+        ));
+        let rows = evaluate(&config);
+        assert!(rows.iter().all(|r| r.a == Some(3.0) && r.b == Some(5.0)));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
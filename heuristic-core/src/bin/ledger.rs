@@ -0,0 +1,267 @@
+// seed ごとの自己ベストの記録 (heuristic_core::ledger) を使って、まとめて動かした結果を相対スコアで見る
+// Usage: ledger --contest NAME [--file PATH] [--seeds FROM TO] [--score PREFIX] [--minimize] [--jobs J]
+//               [--out TEMPLATE] [--input TEMPLATE] [--tolerance R] [-- <command>]
+//   command   : seed ごとに sh -c で実行する ({seed}, {case} の置き換えとスコアの読み方は tune と同じ)
+//   --out     : command が書いた出力のパス ({seed}, {case} を置き換える)。自己ベストと一緒に記録する
+//   --input   : seed の入力ファイルのパス。中身のハッシュを一緒に記録し、入力が変わった seed の自己ベストは捨てる
+//   PATH      : 記録のファイル (default: ledger.txt)。なければ作る
+//   R         : 自己ベストからこの割合より下がった seed を目立たせる (default: 0.01)
+//   seed ごとのスコアと、自己ベストに対する相対スコア (AtCoder の相対評価と同じく 1 が最高) を表示し、
+//   ベストを更新したものは記録に書き込む。command を省くと記録の中身だけ表示する
+//
+// 例 (ahc012 のディレクトリで):
+//   ../target/release/ledger --contest ahc012 --file ../ledger.txt --seeds 0 49 --jobs 4 \
+//     --out 'tools/out/{case}.txt' --input 'tools/in/{case}.txt' -- '../target/release/ahc012 < tools/in/{case}.txt > tools/out/{case}.txt'
+
+use heuristic_core::harness::{expand, par_map, parse_score, run};
+use heuristic_core::ledger::{read_input, report, Ledger, Row, Trial};
+
+struct Config {
+    contest: String,
+    file: String,
+    seeds: Vec<u64>,
+    prefix: Option<String>,
+    minimize: bool,
+    jobs: usize,
+    out: Option<String>,
+    input: Option<String>,
+    tolerance: f64,
+}
+
+// 実行して記録を更新する
+fn record(config: &Config, ledger: &mut Ledger, command: &str) -> Vec<Row> {
+    let command = command.to_string();
+    let prefix = config.prefix.clone();
+    let out = config.out.clone();
+    let input = config.input.clone();
+    let trials = par_map(config.seeds.clone(), config.jobs, move |seed| {
+        let input = match &input {
+            Some(input) => read_input(&expand(input, seed)).map(Some),
+            None => Ok(None),
+        };
+        let score = run(&command, seed, None).and_then(|s| parse_score(&s, prefix.as_deref()));
+        let output = match &out {
+            Some(out) => std::fs::read_to_string(expand(out, seed)).ok(),
+            None => Some(String::new()),
+        };
+        Trial {
+            seed,
+            input,
+            score,
+            output,
+        }
+    });
+
+    ledger.update(&config.contest, trials, config.minimize)
+}
+
+// 記録の中身だけ表示する
+fn show(config: &Config, ledger: &Ledger) {
+    println!(
+        "{:>6}  {:>14}  {:>16}  {:>6}",
+        "seed", "best", "input", "lines"
+    );
+    for seed in ledger.seeds(&config.contest) {
+        let entry = ledger.best(&config.contest, seed, None).unwrap();
+        let input = match entry.input {
+            Some(h) => format!("{:016x}", h),
+            None => "-".to_string(),
+        };
+        println!(
+            "{:>6}  {:>14.3}  {:>16}  {:>6}",
+            seed,
+            entry.score,
+            input,
+            entry.output.lines().count()
+        );
+    }
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: {} --contest NAME [--file PATH] [--seeds FROM TO] [--score PREFIX] [--minimize] [--jobs J] [--out TEMPLATE] [--input TEMPLATE] [--tolerance R] [-- <command>]",
+        std::env::args().next().unwrap()
+    );
+    std::process::exit(1)
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let mut config = Config {
+        contest: String::new(),
+        file: "ledger.txt".to_string(),
+        seeds: (0..10).collect(),
+        prefix: None,
+        minimize: false,
+        jobs: 1,
+        out: None,
+        input: None,
+        tolerance: 0.01,
+    };
+    let mut command = None;
+
+    let mut i = 0;
+    let arg = |i: usize| args.get(i).cloned().unwrap_or_else(|| usage());
+    while i < args.len() {
+        match args[i].as_str() {
+            "--contest" => {
+                config.contest = arg(i + 1);
+                i += 1;
+            }
+            "--file" => {
+                config.file = arg(i + 1);
+                i += 1;
+            }
+            "--seeds" => {
+                let from: u64 = arg(i + 1).parse().unwrap_or_else(|_| usage());
+                let to: u64 = arg(i + 2).parse().unwrap_or_else(|_| usage());
+                config.seeds = (from..=to).collect();
+                i += 2;
+            }
+            "--score" => {
+                config.prefix = Some(arg(i + 1));
+                i += 1;
+            }
+            "--minimize" => config.minimize = true,
+            "--jobs" => {
+                config.jobs = arg(i + 1).parse().unwrap_or_else(|_| usage());
+                i += 1;
+            }
+            "--out" => {
+                config.out = Some(arg(i + 1));
+                i += 1;
+            }
+            "--input" => {
+                config.input = Some(arg(i + 1));
+                i += 1;
+            }
+            "--tolerance" => {
+                config.tolerance = arg(i + 1).parse().unwrap_or_else(|_| usage());
+                i += 1;
+            }
+            "--" => {
+                command = Some(args[i + 1..].join(" "));
+                break;
+            }
+            _ => usage(),
+        }
+        i += 1;
+    }
+    if config.contest.is_empty() || config.contest.contains(char::is_whitespace) {
+        usage();
+    }
+
+    let mut ledger = Ledger::load(&config.file).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1)
+    });
+    match command {
+        Some(command) if !command.is_empty() => {
+            let rows = record(&config, &mut ledger, &command);
+            if let Err(e) = ledger.save(&config.file) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            print!("{}", report(&rows, config.minimize, config.tolerance));
+        }
+        Some(_) => usage(),
+        None => show(&config, &ledger),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(out: Option<String>, input: Option<String>) -> Config {
+        Config {
+            contest: "test".to_string(),
+            file: String::new(),
+            seeds: (0..4).collect(),
+            prefix: None,
+            minimize: false,
+            jobs: 2,
+            out,
+            input,
+            tolerance: 0.01,
+        }
+    }
+
+    #[test]
+    fn records_new_bests_and_flags_regressions() {
+        let config = config(None, None);
+        let mut ledger = Ledger::default();
+        let rows = record(&config, &mut ledger, "echo score: $(( {seed} + 10 ))");
+        assert!(rows.iter().all(|row| row.is_new_best(false)));
+        assert_eq!(ledger.best("test", 3, None).unwrap().score, 13.0);
+
+        // seed 0 は同じ、seed 1 は少し下がり、seed 2 は大きく下がり、seed 3 は上がる
+        let rows = record(
+            &config,
+            &mut ledger,
+            "case {seed} in 0) echo score: 10;; 1) echo score: 10.999;; 2) echo score: 6;; 3) echo score: 26;; esac",
+        );
+        let notes = rows
+            .iter()
+            .map(|row| (row.is_new_best(false), row.is_regression(false, 0.01)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            notes,
+            vec![(false, false), (false, false), (false, true), (true, false)]
+        );
+        assert_eq!(rows[2].relative(false), Some(0.5));
+        assert_eq!(rows[3].relative(false), Some(1.0));
+        assert_eq!(ledger.best("test", 2, None).unwrap().score, 12.0);
+        assert_eq!(ledger.best("test", 3, None).unwrap().score, 26.0);
+    }
+
+    #[test]
+    fn stores_the_output_file() {
+        let dir = std::env::temp_dir().join(format!("ledger-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let out = format!("{}/{{case}}.txt", dir.display());
+        let config = config(Some(out.clone()), None);
+
+        let mut ledger = Ledger::default();
+        let command = format!("echo ans {{seed}} > {} && echo score: {{seed}}", out);
+        let rows = record(&config, &mut ledger, &command);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(ledger.best("test", 2, None).unwrap().output, "ans 2\n");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn drops_bests_of_changed_inputs() {
+        let dir = std::env::temp_dir().join(format!("ledger-input-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for seed in 0..4 {
+            std::fs::write(dir.join(format!("{:04}.txt", seed)), "old\n").unwrap();
+        }
+        let config = config(None, Some(format!("{}/{{case}}.txt", dir.display())));
+
+        let mut ledger = Ledger::default();
+        record(&config, &mut ledger, "echo score: 10");
+        // seed 1 の入力だけ作り直すと、前より低くても新しい自己ベストになる
+        std::fs::write(dir.join("0001.txt"), "new\n").unwrap();
+        let rows = record(&config, &mut ledger, "echo score: 5");
+        let notes = rows
+            .iter()
+            .map(|row| (row.stale, row.is_new_best(false)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            notes,
+            vec![(false, false), (true, true), (false, false), (false, false)]
+        );
+        assert_eq!(ledger.best("test", 1, None).unwrap().score, 5.0);
+        assert_eq!(ledger.best("test", 2, None).unwrap().score, 10.0);
+
+        // 入力が読めない seed は記録しない
+        std::fs::remove_file(dir.join("0003.txt")).unwrap();
+        let rows = record(&config, &mut ledger, "echo score: 20");
+        assert_eq!(rows[3].best, None);
+        assert_eq!(ledger.best("test", 3, None).unwrap().score, 10.0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,467 @@
+// ソルバーの実行時パラメータ (heuristic_core::params) を調整する
+// Usage: tune [--param NAME=LO:HI[:int|:log]]... [--seeds FROM TO] [--search random|halving]
+//             [--trials N] [--score PREFIX] [--minimize] [--jobs J] [--rng-seed S] -- <command>
+//   command : seed ごとに sh -c で実行する。{seed} を seed に、{case} を4桁に0埋めした seed に置き換え、
+//             候補のパラメータは環境変数 PARAMS="name=value,..." で渡す
+//   PREFIX  : 出力 (標準出力と標準エラー) のうち、この文字列で始まる最後の行の数値をスコアにする
+//             (default: "score" で始まる行。"score: 123" も "Score = 123" も読める)
+//   random  : trials 個の設定 (1つ目はソルバーの既定値) を全ての seed で試す (default)
+//   halving : trials 個の設定を少しの seed で試し、上位半分に絞るたびに seed を倍にする (successive halving)
+//   最後に設定ごとの平均と 95% 信頼区間を良い順に表示する
+//
+// 例 (ahc015 のディレクトリで):
+//   ../target/release/tune --param last_search_turn=85:96:int --param ucb_c=0.01:0.2:log \
+//     --search halving --trials 16 --seeds 0 15 --jobs 4 \
+//     -- '../target/release/judge --seed {seed} ../target/release/ahc015'
+
+use heuristic_core::harness::{par_map, parse_score, run};
+use heuristic_core::rng::Rng;
+use heuristic_core::stats::mean_ci;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Scale {
+    Linear,
+    Int,
+    Log,
+}
+
+// 1つのパラメータを探す範囲
+#[derive(Clone, Debug)]
+struct Range {
+    name: String,
+    lo: f64,
+    hi: f64,
+    scale: Scale,
+}
+
+impl Range {
+    // NAME=LO:HI[:int|:log]
+    fn parse(s: &str) -> Option<Self> {
+        let mut kv = s.splitn(2, '=');
+        let name = kv.next()?.trim().to_string();
+        let spec = kv.next()?.split(':').collect::<Vec<_>>();
+        if name.is_empty() || spec.len() < 2 || spec.len() > 3 {
+            return None;
+        }
+        let lo = spec[0].trim().parse::<f64>().ok()?;
+        let hi = spec[1].trim().parse::<f64>().ok()?;
+        let scale = match spec.get(2) {
+            None => Scale::Linear,
+            Some(&"int") => Scale::Int,
+            Some(&"log") => Scale::Log,
+            _ => return None,
+        };
+        if lo > hi || (scale == Scale::Log && lo <= 0.0) {
+            return None;
+        }
+
+        Some(Self {
+            name,
+            lo,
+            hi,
+            scale,
+        })
+    }
+
+    fn sample(&self, rng: &mut Rng) -> String {
+        match self.scale {
+            Scale::Linear => format_value(self.lo + (self.hi - self.lo) * rng.gen_f64()),
+            Scale::Int => rng
+                .gen_range_i64(self.lo.round() as i64, self.hi.round() as i64 + 1)
+                .to_string(),
+            Scale::Log => {
+                let (lo, hi) = (self.lo.ln(), self.hi.ln());
+                format_value((lo + (hi - lo) * rng.gen_f64()).exp())
+            }
+        }
+    }
+}
+
+// 有効数字 4 桁くらいの小数
+fn format_value(x: f64) -> String {
+    if x == 0.0 {
+        return String::from("0");
+    }
+    let digits = (3 - x.abs().log10().floor() as i32).max(0) as usize;
+    format!("{:.*}", digits, x)
+}
+
+// successive halving の各段の (残す設定の数, 使う seed の数)。最後の段で全ての seed を使う
+fn halving_schedule(trials: usize, seed_num: usize) -> Vec<(usize, usize)> {
+    let mut alive = vec![];
+    let mut n = trials.max(1);
+    while n > 1 {
+        alive.push(n);
+        n = n.div_ceil(2);
+    }
+    alive.push(1);
+
+    let rounds = alive.len();
+    alive
+        .into_iter()
+        .enumerate()
+        .map(|(i, n)| {
+            let div = 1usize << (rounds - 1 - i).min(63);
+            (n, seed_num.div_ceil(div).max(1))
+        })
+        .collect()
+}
+
+struct Trial {
+    params: Vec<(String, String)>, // 空なら既定値
+    scores: Vec<(u64, f64)>,       // (seed, スコア)
+    failures: usize,               // スコアが読めなかった回数
+}
+
+impl Trial {
+    fn params_string(&self) -> String {
+        self.params
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    fn label(&self) -> String {
+        if self.params.is_empty() {
+            String::from("(default)")
+        } else {
+            self.params_string()
+        }
+    }
+
+    fn summary(&self, seeds: &[u64]) -> (f64, f64) {
+        let xs = self
+            .scores
+            .iter()
+            .filter(|(s, _)| seeds.contains(s))
+            .map(|&(_, x)| x)
+            .collect::<Vec<_>>();
+        mean_ci(&xs)
+    }
+}
+
+struct Runner {
+    command: String,
+    prefix: Option<String>,
+    jobs: usize,
+}
+
+impl Runner {
+    // 足りない (設定, seed) を jobs 並列で実行して trials に書き込む
+    fn evaluate(&self, trials: &mut [Trial], targets: &[usize], seeds: &[u64]) {
+        let mut tasks = vec![];
+        for &t in targets {
+            for &seed in seeds {
+                if trials[t].scores.iter().all(|(s, _)| *s != seed) {
+                    tasks.push((t, trials[t].params_string(), seed));
+                }
+            }
+        }
+
+        let command = self.command.clone();
+        let prefix = self.prefix.clone();
+        let results = par_map(tasks, self.jobs, move |(t, params, seed)| {
+            let score = run(&command, seed, Some(&params))
+                .and_then(|out| parse_score(&out, prefix.as_deref()));
+            (t, seed, score)
+        });
+
+        for (t, seed, score) in results {
+            match score {
+                Some(score) => trials[t].scores.push((seed, score)),
+                None => {
+                    eprintln!("no score: {} (seed {})", trials[t].label(), seed);
+                    trials[t].failures += 1;
+                    trials[t].scores.push((seed, f64::NAN));
+                }
+            }
+        }
+    }
+}
+
+struct Config {
+    ranges: Vec<Range>,
+    seeds: Vec<u64>,
+    halving: bool,
+    trials: usize,
+    minimize: bool,
+    rng_seed: u64,
+}
+
+// 良い順に並べるための比較 (失敗したものは最後)
+fn compare(config: &Config, a: &Trial, b: &Trial, seeds: &[u64]) -> std::cmp::Ordering {
+    let (ma, _) = a.summary(seeds);
+    let (mb, _) = b.summary(seeds);
+    let key = |f: usize, m: f64| {
+        let m = if m.is_nan() { f64::NEG_INFINITY } else { m };
+        (f == 0, if config.minimize { -m } else { m })
+    };
+    key(b.failures, mb)
+        .partial_cmp(&key(a.failures, ma))
+        .unwrap()
+}
+
+fn tune(config: &Config, runner: &Runner) -> Vec<Trial> {
+    let mut rng = Rng::new(config.rng_seed);
+    let mut trials = (0..config.trials.max(1))
+        .map(|i| Trial {
+            params: if i == 0 {
+                vec![]
+            } else {
+                config
+                    .ranges
+                    .iter()
+                    .map(|r| (r.name.clone(), r.sample(&mut rng)))
+                    .collect()
+            },
+            scores: vec![],
+            failures: 0,
+        })
+        .collect::<Vec<_>>();
+
+    let schedule = if config.halving {
+        halving_schedule(trials.len(), config.seeds.len())
+    } else {
+        vec![(trials.len(), config.seeds.len())]
+    };
+
+    let mut alive = (0..trials.len()).collect::<Vec<_>>();
+    for (round, &(keep, seed_num)) in schedule.iter().enumerate() {
+        let seeds = &config.seeds[..seed_num];
+        // 前の段の順位で上位 keep 個を残す
+        alive.truncate(keep);
+        eprintln!(
+            "round {}: {} configs x {} seeds",
+            round + 1,
+            alive.len(),
+            seeds.len()
+        );
+        runner.evaluate(&mut trials, &alive, seeds);
+        alive.sort_by(|&a, &b| compare(config, &trials[a], &trials[b], seeds));
+    }
+
+    trials
+}
+
+fn report(config: &Config, trials: &[Trial]) {
+    // 多くの seed で試したものを先に、同じ数なら平均の良い順
+    let mut order = (0..trials.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| {
+        trials[b]
+            .scores
+            .len()
+            .cmp(&trials[a].scores.len())
+            .then_with(|| {
+                let seeds = trials[a].scores.iter().map(|&(s, _)| s).collect::<Vec<_>>();
+                compare(config, &trials[a], &trials[b], &seeds)
+            })
+    });
+
+    println!(
+        "{:>4}  {:>14}  {:>12}  {:>5}  params",
+        "rank", "mean", "95% ci", "seeds"
+    );
+    for (rank, &t) in order.iter().enumerate() {
+        let trial = &trials[t];
+        let seeds = trial.scores.iter().map(|&(s, _)| s).collect::<Vec<_>>();
+        let (mean, ci) = trial.summary(&seeds);
+        println!(
+            "{:>4}  {:>14.3}  {:>12.3}  {:>5}  {}{}",
+            rank + 1,
+            mean,
+            ci,
+            seeds.len(),
+            trial.label(),
+            if trial.failures > 0 {
+                format!("  ({} failed)", trial.failures)
+            } else {
+                String::new()
+            }
+        );
+    }
+
+    let best = &trials[order[0]];
+    println!("best: PARAMS=\"{}\"", best.params_string());
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: {} [--param NAME=LO:HI[:int|:log]]... [--seeds FROM TO] [--search random|halving] [--trials N] [--score PREFIX] [--minimize] [--jobs J] [--rng-seed S] -- <command>",
+        std::env::args().next().unwrap()
+    );
+    std::process::exit(1)
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let mut config = Config {
+        ranges: vec![],
+        seeds: (0..10).collect(),
+        halving: false,
+        trials: 20,
+        minimize: false,
+        rng_seed: 0,
+    };
+    let mut prefix = None;
+    let mut jobs = 1;
+    let mut command = None;
+
+    let mut i = 0;
+    let arg = |i: usize| args.get(i).cloned().unwrap_or_else(|| usage());
+    while i < args.len() {
+        match args[i].as_str() {
+            "--param" => {
+                config
+                    .ranges
+                    .push(Range::parse(&arg(i + 1)).unwrap_or_else(|| usage()));
+                i += 1;
+            }
+            "--seeds" => {
+                let from: u64 = arg(i + 1).parse().unwrap_or_else(|_| usage());
+                let to: u64 = arg(i + 2).parse().unwrap_or_else(|_| usage());
+                config.seeds = (from..=to).collect();
+                i += 2;
+            }
+            "--search" => {
+                config.halving = match arg(i + 1).as_str() {
+                    "random" => false,
+                    "halving" => true,
+                    _ => usage(),
+                };
+                i += 1;
+            }
+            "--trials" => {
+                config.trials = arg(i + 1).parse().unwrap_or_else(|_| usage());
+                i += 1;
+            }
+            "--score" => {
+                prefix = Some(arg(i + 1));
+                i += 1;
+            }
+            "--minimize" => config.minimize = true,
+            "--jobs" => {
+                jobs = arg(i + 1).parse().unwrap_or_else(|_| usage());
+                i += 1;
+            }
+            "--rng-seed" => {
+                config.rng_seed = arg(i + 1).parse().unwrap_or_else(|_| usage());
+                i += 1;
+            }
+            "--" => {
+                command = Some(args[i + 1..].join(" "));
+                break;
+            }
+            _ => usage(),
+        }
+        i += 1;
+    }
+    let command = match command {
+        Some(command) if !command.is_empty() && !config.seeds.is_empty() => command,
+        _ => usage(),
+    };
+
+    let runner = Runner {
+        command,
+        prefix,
+        jobs,
+    };
+    let trials = tune(&config, &runner);
+    report(&config, &trials);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ranges() {
+        let r = Range::parse("ucb_c=0.01:0.2:log").unwrap();
+        assert_eq!(r.name, "ucb_c");
+        assert_eq!((r.lo, r.hi, r.scale), (0.01, 0.2, Scale::Log));
+        assert_eq!(Range::parse("k=80:100:int").unwrap().scale, Scale::Int);
+        assert_eq!(Range::parse("t=0:1").unwrap().scale, Scale::Linear);
+
+        assert!(Range::parse("k=100:80").is_none());
+        assert!(Range::parse("c=0:1:log").is_none());
+        assert!(Range::parse("k=1:2:cube").is_none());
+        assert!(Range::parse("=1:2").is_none());
+        assert!(Range::parse("k").is_none());
+    }
+
+    #[test]
+    fn samples_stay_in_range() {
+        let mut rng = Rng::new(0);
+        let ranges = [
+            Range::parse("a=80:100:int").unwrap(),
+            Range::parse("b=1e-6:1e-3:log").unwrap(),
+            Range::parse("c=-1:1").unwrap(),
+        ];
+        for _ in 0..1000 {
+            for r in &ranges {
+                let s = r.sample(&mut rng);
+                let v: f64 = s.parse().unwrap();
+                assert!(r.lo * 0.999 <= v && v <= r.hi * 1.001, "{} {}", r.name, s);
+                if r.scale == Scale::Int {
+                    assert!(!s.contains('.'));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn formats_values_with_four_significant_digits() {
+        assert_eq!(format_value(0.0123456), "0.01235");
+        assert_eq!(format_value(1.23456), "1.235");
+        assert_eq!(format_value(12345.6), "12346");
+        assert_eq!(format_value(5.1234e-6), "0.000005123");
+        assert_eq!(format_value(0.0), "0");
+    }
+
+    #[test]
+    fn halving_doubles_seeds_while_halving_configs() {
+        assert_eq!(
+            halving_schedule(16, 16),
+            vec![(16, 1), (8, 2), (4, 4), (2, 8), (1, 16)]
+        );
+        assert_eq!(
+            halving_schedule(10, 5),
+            vec![(10, 1), (5, 1), (3, 2), (2, 3), (1, 5)]
+        );
+        assert_eq!(halving_schedule(1, 7), vec![(1, 7)]);
+    }
+
+    #[test]
+    fn halving_keeps_the_best_config() {
+        // PARAMS の x が 7 に近いほど良い
+        let config = Config {
+            ranges: vec![Range::parse("x=0:10:int").unwrap()],
+            seeds: (0..4).collect(),
+            halving: true,
+            trials: 8,
+            minimize: false,
+            rng_seed: 1,
+        };
+        let runner = Runner {
+            command: String::from(
+                "x=$(echo \"$PARAMS\" | sed -n 's/^x=//p'); x=${x:-0}; echo \"score: $(( 100 - (x - 7) * (x - 7) + {seed} ))\"",
+            ),
+            prefix: None,
+            jobs: 3,
+        };
+        let trials = tune(&config, &runner);
+
+        let full = trials
+            .iter()
+            .filter(|t| t.scores.len() == 4)
+            .collect::<Vec<_>>();
+        assert_eq!(full.len(), 1);
+        let best_x = trials
+            .iter()
+            .filter_map(|t| t.params.first().map(|(_, v)| v.parse::<i64>().unwrap()))
+            .min_by_key(|x| (x - 7).abs())
+            .unwrap();
+        assert_eq!(full[0].params[0].1.parse::<i64>().unwrap(), best_x);
+        assert!(trials.iter().all(|t| t.failures == 0));
+    }
+}
@@ -0,0 +1,92 @@
+// 格子上の座標
+
+// 上下左右
+pub const DELTA4: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Coord {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Coord {
+    pub fn new(p: (isize, isize)) -> Self {
+        Coord { x: p.0, y: p.1 }
+    }
+    pub fn from_usize_pair(p: (usize, usize)) -> Self {
+        Coord {
+            x: p.0 as isize,
+            y: p.1 as isize,
+        }
+    }
+
+    // [0, w) × [0, h) に入っているか
+    pub fn in_rect(&self, w: usize, h: usize) -> bool {
+        (0 <= self.x && self.x < w as isize) && (0 <= self.y && self.y < h as isize)
+    }
+
+    // ペアへの変換
+    pub fn to_pair(&self) -> (isize, isize) {
+        (self.x, self.y)
+    }
+    pub fn to_usize_pair(&self) -> (usize, usize) {
+        (self.x as usize, self.y as usize)
+    }
+
+    // マンハッタン距離
+    pub fn distance(&self, that: &Self) -> isize {
+        (self.x - that.x).abs() + (self.y - that.y).abs()
+    }
+
+    // ユークリッド距離の2乗
+    pub fn sq_distance(&self, that: &Self) -> i64 {
+        let dx = (self.x - that.x) as i64;
+        let dy = (self.y - that.y) as i64;
+        dx * dx + dy * dy
+    }
+
+    // 四則演算
+    pub fn plus(&self, that: &Self) -> Self {
+        Coord::new((self.x + that.x, self.y + that.y))
+    }
+    pub fn minus(&self, that: &Self) -> Self {
+        Coord::new((self.x - that.x, self.y - that.y))
+    }
+
+    // 原点を始点とするベクトルとしての外積
+    pub fn cross(&self, that: &Self) -> i64 {
+        self.x as i64 * that.y as i64 - self.y as i64 * that.x as i64
+    }
+
+    // [0, w) × [0, h) に収まる上下左右の隣
+    pub fn mk_4dir(&self, w: usize, h: usize) -> Vec<Self> {
+        DELTA4
+            .iter()
+            .map(|&d| self.plus(&Coord::new(d)))
+            .filter(|pos| pos.in_rect(w, h))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic_and_distance() {
+        let a = Coord::new((1, -2));
+        let b = Coord::new((-3, 4));
+        assert_eq!(a.plus(&b), Coord::new((-2, 2)));
+        assert_eq!(a.minus(&b), Coord::new((4, -6)));
+        assert_eq!(a.distance(&b), 10);
+        assert_eq!(a.sq_distance(&b), 52);
+        assert_eq!(a.cross(&b), 4 - 6);
+    }
+
+    #[test]
+    fn neighbors_stay_inside() {
+        assert_eq!(Coord::new((0, 0)).mk_4dir(3, 3).len(), 2);
+        assert_eq!(Coord::new((1, 1)).mk_4dir(3, 3).len(), 4);
+        assert_eq!(Coord::new((2, 0)).mk_4dir(3, 1).len(), 1);
+    }
+}
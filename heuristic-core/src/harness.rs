@@ -0,0 +1,303 @@
+// ローカルでソルバーを seed ごとに動かしてスコアを読む (tune, compare, ledger, ahc から使う。提出物には入らない)
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, Arc, Mutex};
+
+// {seed} を seed に、{case} を4桁に0埋めした seed に置き換える
+pub fn expand(command: &str, seed: u64) -> String {
+    command
+        .replace("{seed}", &seed.to_string())
+        .replace("{case}", &format!("{:04}", seed))
+}
+
+// sh -c で実行して標準出力と標準エラーをつなげたものを返す。params は環境変数 PARAMS で渡す
+pub fn run(command: &str, seed: u64, params: Option<&str>) -> Option<String> {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(expand(command, seed));
+    if let Some(params) = params {
+        cmd.env("PARAMS", params);
+    }
+    let out = cmd.output().ok()?;
+
+    Some(format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&out.stdout),
+        String::from_utf8_lossy(&out.stderr)
+    ))
+}
+
+// sh を通さずに動かすプロセス。作業ディレクトリと標準入出力のファイルも持つ
+// stdin, stdout のパスは dir からの相対で、stdout が None なら標準出力は捕まえて返す
+#[derive(Debug, Clone, PartialEq)]
+pub struct Job {
+    pub program: String,
+    pub args: Vec<String>,
+    pub dir: Option<String>,
+    pub stdin: Option<String>,
+    pub stdout: Option<String>,
+    pub env: Vec<(String, String)>,
+}
+
+impl Job {
+    pub fn new(program: &str) -> Self {
+        Self {
+            program: program.to_string(),
+            args: vec![],
+            dir: None,
+            stdin: None,
+            stdout: None,
+            env: vec![],
+        }
+    }
+
+    pub fn args(mut self, args: &[&str]) -> Self {
+        self.args.extend(args.iter().map(|a| a.to_string()));
+        self
+    }
+
+    pub fn dir(mut self, dir: &str) -> Self {
+        self.dir = Some(dir.to_string());
+        self
+    }
+
+    pub fn stdin(mut self, path: &str) -> Self {
+        self.stdin = Some(path.to_string());
+        self
+    }
+
+    pub fn stdout(mut self, path: &str) -> Self {
+        self.stdout = Some(path.to_string());
+        self
+    }
+
+    pub fn env(mut self, name: &str, value: &str) -> Self {
+        self.env.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    // プログラム、引数、パス、環境変数の値を全て f で書き換える
+    pub fn map(&self, f: impl Fn(&str) -> String) -> Self {
+        Self {
+            program: f(&self.program),
+            args: self.args.iter().map(|a| f(a)).collect(),
+            dir: self.dir.as_deref().map(&f),
+            stdin: self.stdin.as_deref().map(&f),
+            stdout: self.stdout.as_deref().map(&f),
+            env: self.env.iter().map(|(k, v)| (k.clone(), f(v))).collect(),
+        }
+    }
+
+    // {seed}, {case} を置き換える
+    pub fn expand(&self, seed: u64) -> Self {
+        self.map(|s| expand(s, seed))
+    }
+
+    fn path(&self, path: &str) -> PathBuf {
+        match &self.dir {
+            Some(dir) => Path::new(dir).join(path),
+            None => PathBuf::from(path),
+        }
+    }
+
+    fn command(&self, params: Option<&str>) -> Result<Command, String> {
+        let mut cmd = Command::new(&self.program);
+        cmd.args(&self.args);
+        if let Some(dir) = &self.dir {
+            cmd.current_dir(dir);
+        }
+        cmd.envs(self.env.iter().map(|(k, v)| (k, v)));
+        if let Some(params) = params {
+            cmd.env("PARAMS", params);
+        }
+        if let Some(stdin) = &self.stdin {
+            let path = self.path(stdin);
+            let file = File::open(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            cmd.stdin(file);
+        }
+        if let Some(stdout) = &self.stdout {
+            let path = self.path(stdout);
+            let file = File::create(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            cmd.stdout(file);
+        }
+
+        Ok(cmd)
+    }
+
+    // 標準入出力をつないだまま動かす
+    pub fn status(&self, params: Option<&str>) -> Result<(), String> {
+        let status = self
+            .command(params)?
+            .status()
+            .map_err(|e| format!("{}: {}", self.program, e))?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(format!("{} failed ({})", self, status))
+        }
+    }
+
+    // 動かして標準出力 (ファイルに書くなら空) と標準エラーをつなげたものを返す。終了コードは見ない
+    pub fn output(&self, params: Option<&str>) -> Result<String, String> {
+        let mut cmd = self.command(params)?;
+        if self.stdout.is_none() {
+            cmd.stdout(Stdio::piped());
+        }
+        let out = cmd
+            .stderr(Stdio::piped())
+            .output()
+            .map_err(|e| format!("{}: {}", self.program, e))?;
+
+        Ok(format!(
+            "{}\n{}",
+            String::from_utf8_lossy(&out.stdout),
+            String::from_utf8_lossy(&out.stderr)
+        ))
+    }
+}
+
+impl std::fmt::Display for Job {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.program)?;
+        for arg in &self.args {
+            write!(f, " {}", arg)?;
+        }
+        if let Some(stdin) = &self.stdin {
+            write!(f, " < {}", stdin)?;
+        }
+        if let Some(stdout) = &self.stdout {
+            write!(f, " > {}", stdout)?;
+        }
+
+        Ok(())
+    }
+}
+
+// 出力からスコアを読む (該当する最後の行)
+// prefix がなければ "score" で始まる行 ("score: 123" も "Score = 123" も読める)
+pub fn parse_score(output: &str, prefix: Option<&str>) -> Option<f64> {
+    output.lines().rev().find_map(|line| {
+        let line = line.trim();
+        let rest = match prefix {
+            Some(prefix) => line.strip_prefix(prefix)?,
+            None => {
+                if !line.to_lowercase().starts_with("score") {
+                    return None;
+                }
+                line["score".len()..].trim_start_matches([':', '=', ' '])
+            }
+        };
+        rest.split_whitespace().next()?.parse().ok()
+    })
+}
+
+// jobs 並列で f を適用する (結果は items の順)
+pub fn par_map<T, R, F>(items: Vec<T>, jobs: usize, f: F) -> Vec<R>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+    F: Fn(T) -> R + Send + Sync + 'static,
+{
+    let n = items.len();
+    let mut queue = items.into_iter().enumerate().collect::<Vec<_>>();
+    queue.reverse();
+    let queue = Arc::new(Mutex::new(queue));
+    let f = Arc::new(f);
+
+    let (tx, rx) = mpsc::channel();
+    let handles = (0..jobs.max(1))
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            let f = Arc::clone(&f);
+            let tx = tx.clone();
+            std::thread::spawn(move || loop {
+                let item = queue.lock().unwrap().pop();
+                match item {
+                    Some((i, item)) => tx.send((i, f(item))).unwrap(),
+                    None => break,
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    drop(tx);
+
+    let mut res = (0..n).map(|_| None).collect::<Vec<_>>();
+    for (i, r) in rx.iter() {
+        res[i] = Some(r);
+    }
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    res.into_iter().map(|r| r.unwrap()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_last_score_line() {
+        let out = "start annealing at 3ms.\nscore: 12\n901ms\nscore: 34.5\n";
+        assert_eq!(parse_score(out, None), Some(34.5));
+        assert_eq!(parse_score("Score = 752345\n", None), Some(752345.0));
+        assert_eq!(parse_score("cost 7\nscore: 1\n", Some("cost ")), Some(7.0));
+        assert_eq!(parse_score("1784 * 1000回ループ\n", None), None);
+    }
+
+    #[test]
+    fn expands_placeholders_and_passes_params() {
+        assert_eq!(
+            expand("gen {seed} > in/{case}.txt", 7),
+            "gen 7 > in/0007.txt"
+        );
+        let out = run("echo \"score: {seed} $PARAMS\"", 3, Some("k=1")).unwrap();
+        assert_eq!(out.lines().next(), Some("score: 3 k=1"));
+        assert_eq!(parse_score(&out, None), Some(3.0));
+    }
+
+    #[test]
+    fn jobs_redirect_files_without_a_shell() {
+        let dir = std::env::temp_dir().join(format!("harness-job-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("in")).unwrap();
+        std::fs::write(dir.join("in/0007.txt"), "7 $PARAMS\n").unwrap();
+        let dir = dir.display().to_string();
+
+        // 引数は sh に解釈されない
+        let job = Job::new("cat")
+            .dir(&dir)
+            .stdin("in/{case}.txt")
+            .stdout("out-{seed}.txt")
+            .expand(7);
+        assert_eq!(job.stdin.as_deref(), Some("in/0007.txt"));
+        job.status(None).unwrap();
+        let out = std::fs::read_to_string(format!("{}/out-7.txt", dir)).unwrap();
+        assert_eq!(out, "7 $PARAMS\n");
+
+        let out = Job::new("pwd").dir(&dir).output(None).unwrap();
+        assert!(out
+            .trim()
+            .ends_with(&format!("harness-job-test-{}", std::process::id())));
+        let out = Job::new("printenv")
+            .args(&["PARAMS", "LOG"])
+            .env("LOG", "{case}")
+            .expand(3)
+            .output(Some("k=1"))
+            .unwrap();
+        assert_eq!(out.lines().take(2).collect::<Vec<_>>(), vec!["k=1", "0003"]);
+
+        // 入力が無ければ動かさない
+        let missing = Job::new("cat").dir(&dir).stdin("in/0000.txt");
+        assert!(missing.status(None).is_err());
+        assert!(Job::new("false").status(None).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn par_map_keeps_the_order() {
+        let res = par_map((0..50).collect(), 4, |x: u64| x * x);
+        assert_eq!(res, (0..50).map(|x| x * x).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,59 @@
+// 空白区切りのトークンを読む入出力 (対話型の問題でも1トークンずつ読める。stdin().lock() を渡す)
+
+use std::io::{BufRead, BufWriter, Read, Write};
+
+pub struct IO<R, W: Write>(R, BufWriter<W>);
+
+impl<R: BufRead, W: Write> IO<R, W> {
+    pub fn new(r: R, w: W) -> IO<R, W> {
+        IO(r, BufWriter::new(w))
+    }
+    pub fn write<S: ToString>(&mut self, s: S) {
+        self.1.write_all(s.to_string().as_bytes()).unwrap();
+    }
+    // 対話型ではジャッジに渡す前に呼ぶ
+    pub fn flush(&mut self) {
+        self.1.flush().unwrap();
+    }
+    pub fn read<T: std::str::FromStr>(&mut self) -> T {
+        let buf = self
+            .0
+            .by_ref()
+            .bytes()
+            .map(|b| b.unwrap())
+            .skip_while(|&b| b == b' ' || b == b'\n' || b == b'\r' || b == b'\t')
+            .take_while(|&b| b != b' ' && b != b'\n' && b != b'\r' && b != b'\t')
+            .collect::<Vec<_>>();
+        std::str::from_utf8(&buf)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .expect("Parse error.")
+    }
+    pub fn vec<T: std::str::FromStr>(&mut self, n: usize) -> Vec<T> {
+        (0..n).map(|_| self.read()).collect()
+    }
+    pub fn chars(&mut self) -> Vec<char> {
+        self.read::<String>().chars().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_tokens_across_lines() {
+        let input = b"3 -4\n  abc\r\n1 2 3\n";
+        let mut out = vec![];
+        {
+            let mut io = IO::new(&input[..], &mut out);
+            assert_eq!(io.read::<usize>(), 3);
+            assert_eq!(io.read::<i64>(), -4);
+            assert_eq!(io.chars(), vec!['a', 'b', 'c']);
+            assert_eq!(io.vec::<u32>(3), vec![1, 2, 3]);
+            io.write("ok\n");
+            io.flush();
+        }
+        assert_eq!(out, b"ok\n");
+    }
+}
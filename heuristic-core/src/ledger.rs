@@ -0,0 +1,417 @@
+// コンテストと seed ごとの自己ベスト (スコアとそのときの出力) の記録 (ローカル用。提出物には入らない)
+// ファイルはテキストで、1件ごとに見出し行 "<contest> <seed> <score> <入力のハッシュ> <出力の行数>" と出力そのものを並べる。
+// 入力のハッシュが分からなければ "-" (古い4列の見出し行もそう読む)。# で始まる見出し行は読み飛ばす。
+// 入力を作り直すと同じ seed でも別の問題になるので、ハッシュが合わない記録は自己ベストとして使わない
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+const HEADER: &str = "# contest seed score input lines (続く lines 行がそのスコアの出力)";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub score: f64,
+    pub input: Option<u64>, // 入力ファイルのハッシュ (hash_input)
+    pub output: String,
+}
+
+// 入力ファイルの中身のハッシュ (FNV-1a)。ビルドやプラットフォームによらず同じ値になる
+pub fn hash_input(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Ledger {
+    entries: BTreeMap<(String, u64), Entry>,
+}
+
+impl Ledger {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut entries = BTreeMap::new();
+        let mut lines = s.lines().enumerate();
+        while let Some((i, line)) = lines.next() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let v = line.split_whitespace().collect::<Vec<_>>();
+            let invalid = || format!("line {}: invalid entry: {}", i + 1, line);
+            let (input, len) = match v.len() {
+                4 => (None, v[3]),
+                5 if v[3] == "-" => (None, v[4]),
+                5 => (
+                    Some(u64::from_str_radix(v[3], 16).map_err(|_| invalid())?),
+                    v[4],
+                ),
+                _ => return Err(invalid()),
+            };
+            let seed = v[1].parse::<u64>().map_err(|_| invalid())?;
+            let score = v[2].parse::<f64>().map_err(|_| invalid())?;
+            let len = len.parse::<usize>().map_err(|_| invalid())?;
+
+            let mut output = String::new();
+            for _ in 0..len {
+                let (_, line) = lines
+                    .next()
+                    .ok_or_else(|| format!("line {}: output is truncated", i + 1))?;
+                output.push_str(line);
+                output.push('\n');
+            }
+            entries.insert(
+                (v[0].to_string(), seed),
+                Entry {
+                    score,
+                    input,
+                    output,
+                },
+            );
+        }
+
+        Ok(Self { entries })
+    }
+
+    // ファイルがなければ空から始める
+    pub fn load(path: &str) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(s) => Self::parse(&s).map_err(|e| format!("{}: {}", path, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("{}: {}", path, e)),
+        }
+    }
+
+    // 途中で止まっても壊れないよう、別のファイルに書いてから置き換える
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let tmp = format!("{}.tmp", path);
+        std::fs::write(&tmp, self.to_string())
+            .and_then(|_| std::fs::rename(&tmp, path))
+            .map_err(|e| format!("{}: {}", path, e))
+    }
+
+    // input が Some なら、その入力での記録だけ返す (ハッシュが分からない記録も返さない)
+    pub fn best(&self, contest: &str, seed: u64, input: Option<u64>) -> Option<&Entry> {
+        self.entries
+            .get(&(contest.to_string(), seed))
+            .filter(|e| input.is_none() || e.input == input)
+    }
+
+    // 記録はあるが、入力が変わっていて使えない
+    pub fn is_stale(&self, contest: &str, seed: u64, input: Option<u64>) -> bool {
+        self.entries.contains_key(&(contest.to_string(), seed))
+            && self.best(contest, seed, input).is_none()
+    }
+
+    pub fn seeds(&self, contest: &str) -> Vec<u64> {
+        self.entries
+            .keys()
+            .filter(|(c, _)| c == contest)
+            .map(|&(_, seed)| seed)
+            .collect()
+    }
+
+    // 自己ベストを更新したら記録して true (同点なら前のまま)。
+    // 入力が変わっていたら前の記録は捨てて置き換える
+    pub fn record(
+        &mut self,
+        contest: &str,
+        seed: u64,
+        input: Option<u64>,
+        score: f64,
+        output: &str,
+        minimize: bool,
+    ) -> bool {
+        if let Some(best) = self.best(contest, seed, input) {
+            if !is_better(score, best.score, minimize) {
+                return false;
+            }
+        }
+        let mut output = output.to_string();
+        if !output.is_empty() && !output.ends_with('\n') {
+            output.push('\n');
+        }
+        self.entries.insert(
+            (contest.to_string(), seed),
+            Entry {
+                score,
+                input,
+                output,
+            },
+        );
+
+        true
+    }
+
+    // まとめて動かした結果で自己ベストを更新する。入力と突き合わせられない結果は記録しない
+    pub fn update(&mut self, contest: &str, trials: Vec<Trial>, minimize: bool) -> Vec<Row> {
+        let mut rows = vec![];
+        for trial in trials {
+            let seed = trial.seed;
+            let input = match trial.input {
+                Ok(input) => input,
+                Err(e) => {
+                    eprintln!("cannot read the input (seed {}): {}", seed, e);
+                    rows.push(Row {
+                        seed,
+                        score: trial.score,
+                        prev: None,
+                        best: None,
+                        stale: false,
+                    });
+                    continue;
+                }
+            };
+            let stale = self.is_stale(contest, seed, input);
+            let prev = self.best(contest, seed, input).map(|e| e.score);
+            match (trial.score, trial.output) {
+                (Some(score), Some(output)) => {
+                    self.record(contest, seed, input, score, &output, minimize);
+                }
+                (Some(_), None) => eprintln!("cannot read the output (seed {})", seed),
+                _ => eprintln!("no score (seed {})", seed),
+            }
+            let best = self.best(contest, seed, input).map(|e| e.score);
+            rows.push(Row {
+                seed,
+                score: trial.score,
+                prev,
+                best,
+                stale,
+            });
+        }
+
+        rows
+    }
+}
+
+impl std::fmt::Display for Ledger {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        for ((contest, seed), entry) in &self.entries {
+            let input = match entry.input {
+                Some(h) => format!("{:016x}", h),
+                None => "-".to_string(),
+            };
+            writeln!(
+                f,
+                "{} {} {} {} {}",
+                contest,
+                seed,
+                entry.score,
+                input,
+                entry.output.lines().count()
+            )?;
+            write!(f, "{}", entry.output)?;
+        }
+
+        Ok(())
+    }
+}
+
+// 入力ファイルを読んでハッシュを取る
+pub fn read_input(path: &str) -> Result<u64, String> {
+    std::fs::read_to_string(path)
+        .map(|s| hash_input(&s))
+        .map_err(|e| format!("{}: {}", path, e))
+}
+
+// 1つの seed を1回動かした結果
+#[derive(Debug, Clone)]
+pub struct Trial {
+    pub seed: u64,
+    pub input: Result<Option<u64>, String>, // 入力のハッシュ (入力ファイルを使わないなら None)
+    pub score: Option<f64>,
+    pub output: Option<String>, // 記録する出力 (読めなければ None)
+}
+
+// 1つの seed の結果と自己ベスト
+#[derive(Debug)]
+pub struct Row {
+    pub seed: u64,
+    pub score: Option<f64>,
+    pub prev: Option<f64>, // 今回より前の自己ベスト
+    pub best: Option<f64>, // 今回を含めた自己ベスト
+    pub stale: bool,       // 入力が変わったので前の自己ベストを捨てた
+}
+
+impl Row {
+    pub fn relative(&self, minimize: bool) -> Option<f64> {
+        Some(relative(self.score?, self.best?, minimize))
+    }
+
+    pub fn is_new_best(&self, minimize: bool) -> bool {
+        match (self.score, self.prev) {
+            (Some(score), Some(prev)) => is_better(score, prev, minimize),
+            (Some(_), None) => true,
+            _ => false,
+        }
+    }
+
+    // 前の自己ベストから tolerance より下がった
+    pub fn is_regression(&self, minimize: bool, tolerance: f64) -> bool {
+        match (self.score, self.prev) {
+            (Some(score), Some(prev)) => relative(score, prev, minimize) < 1.0 - tolerance,
+            _ => false,
+        }
+    }
+}
+
+// seed ごとのスコアと自己ベストに対する相対スコアの表、最後に全体の集計
+pub fn report(rows: &[Row], minimize: bool, tolerance: f64) -> String {
+    let show = |x: Option<f64>, digits: usize| match x {
+        Some(x) => format!("{:.*}", digits, x),
+        None => "-".to_string(),
+    };
+
+    let mut s = String::new();
+    writeln!(
+        s,
+        "{:>6}  {:>14}  {:>14}  {:>8}  note",
+        "seed", "score", "prev best", "relative"
+    )
+    .unwrap();
+    for row in rows {
+        let note = if row.is_regression(minimize, tolerance) {
+            "REGRESSION"
+        } else if row.stale && row.is_new_best(minimize) {
+            "new best (input changed)"
+        } else if row.is_new_best(minimize) {
+            "new best"
+        } else {
+            ""
+        };
+        writeln!(
+            s,
+            "{:>6}  {:>14}  {:>14}  {:>8}  {}",
+            row.seed,
+            show(row.score, 3),
+            show(row.prev, 3),
+            show(row.relative(minimize), 4),
+            note
+        )
+        .unwrap();
+    }
+
+    let relatives = rows
+        .iter()
+        .map(|row| row.relative(minimize).unwrap_or(0.0))
+        .collect::<Vec<_>>();
+    let count = |f: &dyn Fn(&Row) -> bool| rows.iter().filter(|row| f(row)).count();
+    writeln!(
+        s,
+        "relative: {:.4} (x{} seeds = {:.3})  new best: {}  regressions: {}  failed: {}",
+        relatives.iter().sum::<f64>() / rows.len().max(1) as f64,
+        rows.len(),
+        relatives.iter().sum::<f64>(),
+        count(&|row| row.is_new_best(minimize)),
+        count(&|row| row.is_regression(minimize, tolerance)),
+        count(&|row| row.score.is_none())
+    )
+    .unwrap();
+
+    s
+}
+
+pub fn is_better(score: f64, than: f64, minimize: bool) -> bool {
+    if minimize {
+        score < than
+    } else {
+        score > than
+    }
+}
+
+// AtCoder の相対評価と同じく、ベストを 1 としたときのスコア (最小化なら best / score)
+pub fn relative(score: f64, best: f64, minimize: bool) -> f64 {
+    let (num, den) = if minimize {
+        (best, score)
+    } else {
+        (score, best)
+    };
+    if den == 0.0 {
+        return if num == 0.0 { 1.0 } else { 0.0 };
+    }
+
+    num / den
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_text() {
+        let mut ledger = Ledger::default();
+        let input = Some(hash_input("2 100\n"));
+        assert!(ledger.record("ahc012", 3, input, 763636.0, "2\n1 2 3 4\n5 6 7 8", false));
+        assert!(ledger.record("ahc015", 0, None, 752345.0, "", false));
+        assert!(ledger.record("ahc001", 10, None, 0.97, "# コメントも出力の一部\n", false));
+
+        let parsed = Ledger::parse(&ledger.to_string()).unwrap();
+        assert_eq!(parsed.seeds("ahc012"), vec![3]);
+        assert_eq!(
+            parsed.best("ahc012", 3, input),
+            Some(&Entry {
+                score: 763636.0,
+                input,
+                output: "2\n1 2 3 4\n5 6 7 8\n".to_string()
+            })
+        );
+        assert_eq!(parsed.best("ahc015", 0, None).unwrap().output, "");
+        assert_eq!(
+            parsed.best("ahc001", 10, None).unwrap().output,
+            "# コメントも出力の一部\n"
+        );
+        assert_eq!(parsed.best("ahc015", 1, None), None);
+
+        // ハッシュの無い古い見出し行も読める
+        let old = Ledger::parse("ahc012 0 1.5 1\nout\n").unwrap();
+        assert_eq!(old.best("ahc012", 0, None).unwrap().input, None);
+    }
+
+    #[test]
+    fn keeps_only_the_best() {
+        let mut ledger = Ledger::default();
+        assert!(ledger.record("ahc012", 0, None, 100.0, "a", false));
+        assert!(!ledger.record("ahc012", 0, None, 90.0, "b", false));
+        assert!(!ledger.record("ahc012", 0, None, 100.0, "c", false));
+        assert!(ledger.record("ahc012", 0, None, 110.0, "d", false));
+        assert_eq!(ledger.best("ahc012", 0, None).unwrap().output, "d\n");
+
+        assert!(ledger.record("cost", 0, None, 10.0, "", true));
+        assert!(ledger.record("cost", 0, None, 8.0, "", true));
+        assert_eq!(ledger.best("cost", 0, None).unwrap().score, 8.0);
+    }
+
+    #[test]
+    fn resets_when_the_input_changes() {
+        let (old, new) = (Some(hash_input("old\n")), Some(hash_input("new\n")));
+        let mut ledger = Ledger::default();
+        assert!(ledger.record("ahc012", 0, old, 100.0, "a", false));
+        assert!(!ledger.is_stale("ahc012", 0, old));
+        assert!(ledger.is_stale("ahc012", 0, new));
+        assert_eq!(ledger.best("ahc012", 0, new), None);
+
+        // 前より悪くても、別の入力の記録なので置き換える
+        assert!(ledger.record("ahc012", 0, new, 50.0, "b", false));
+        assert_eq!(ledger.best("ahc012", 0, new).unwrap().score, 50.0);
+        assert_eq!(ledger.best("ahc012", 0, old), None);
+        assert_eq!(hash_input(""), 0xcbf2_9ce4_8422_2325);
+    }
+
+    #[test]
+    fn relative_scores() {
+        assert_eq!(relative(80.0, 100.0, false), 0.8);
+        assert_eq!(relative(125.0, 100.0, true), 0.8);
+        assert_eq!(relative(0.0, 0.0, false), 1.0);
+    }
+
+    #[test]
+    fn reports_broken_files() {
+        assert!(Ledger::parse("ahc012 0 1.5\n").is_err());
+        assert!(Ledger::parse("ahc012 0 1.5 2\nonly one line\n").is_err());
+        assert!(Ledger::parse("ahc012 0 1.5 xyz 0\n").is_err());
+        assert!(Ledger::parse("# 空\n\n")
+            .unwrap()
+            .seeds("ahc012")
+            .is_empty());
+    }
+}
@@ -0,0 +1,15 @@
+// コンテストをまたいで使う部品
+// 提出するときは a.rs に埋め込む (外部クレートには依存しない)
+
+pub mod anneal;
+pub mod beam;
+pub mod geom;
+pub mod harness;
+pub mod io;
+pub mod ledger;
+pub mod mcts;
+pub mod movie;
+pub mod params;
+pub mod rng;
+pub mod stats;
+pub mod timer;
@@ -0,0 +1,475 @@
+// モンテカルロ木探索 (UCT) の枠組み
+// 1人ゲームで、手番 (Decision) と偶然の出来事 (Chance) が交互に来るものを扱う。
+// 問題ごとに Game を実装し、プレイアウトの方策 (Rollout) を選んで Mcts::search に渡す。価値は最大化する
+
+use crate::rng::Rng;
+pub use crate::timer::Budget;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Decision,
+    Chance,
+    Terminal,
+}
+
+pub trait Game: Clone {
+    type Action: Clone + PartialEq;
+    type Event: Clone + PartialEq;
+
+    fn phase(&self) -> Phase;
+
+    // Decision で選べる手
+    fn actions(&self) -> Vec<Self::Action>;
+    fn apply(&mut self, action: &Self::Action);
+
+    // Chance で起こる出来事を1つ引く
+    fn sample_event(&self, rng: &mut Rng) -> Self::Event;
+    fn apply_event(&mut self, event: &Self::Event);
+
+    // 終局 (またはプレイアウトを打ち切ったところ) の価値。UCT の係数と釣り合う大きさにする
+    fn value(&self) -> f64;
+}
+
+pub trait Rollout<G: Game> {
+    // game から最後まで (または適当なところまで) 進めて価値を返す
+    fn rollout(&self, game: &G, rng: &mut Rng) -> f64;
+}
+
+// 一様ランダムに手を選ぶ
+pub struct RandomRollout;
+impl<G: Game> Rollout<G> for RandomRollout {
+    fn rollout(&self, game: &G, rng: &mut Rng) -> f64 {
+        let mut game = game.clone();
+        loop {
+            match game.phase() {
+                Phase::Decision => {
+                    let actions = game.actions();
+                    let action = rng.choose(&actions).unwrap().clone();
+                    game.apply(&action);
+                }
+                Phase::Chance => {
+                    let event = game.sample_event(rng);
+                    game.apply_event(&event);
+                }
+                Phase::Terminal => return game.value(),
+            }
+        }
+    }
+}
+
+enum Kind<G: Game> {
+    Decision {
+        children: Vec<(G::Action, usize)>,
+        untried: Vec<G::Action>,
+    },
+    Chance {
+        children: Vec<(G::Event, usize)>,
+    },
+    Terminal,
+}
+
+struct Node<G: Game> {
+    visits: usize,
+    sum: f64,
+    kind: Kind<G>,
+}
+
+impl<G: Game> Node<G> {
+    fn new(game: &G) -> Self {
+        let kind = match game.phase() {
+            Phase::Decision => {
+                let mut untried = game.actions();
+                untried.reverse();
+                Kind::Decision {
+                    children: vec![],
+                    untried,
+                }
+            }
+            Phase::Chance => Kind::Chance { children: vec![] },
+            Phase::Terminal => Kind::Terminal,
+        };
+
+        Self {
+            visits: 0,
+            sum: 0.0,
+            kind,
+        }
+    }
+
+    fn mean(&self) -> f64 {
+        if self.visits == 0 {
+            0.0
+        } else {
+            self.sum / self.visits as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult<A> {
+    pub action: A,
+    pub children: Vec<(A, usize, f64)>, // 根の子ごとの (手, 訪問回数, 平均)
+    pub iterations: usize,
+    pub nodes: usize,
+}
+
+pub struct Mcts {
+    c: f64, // UCT の探索項の係数
+    budget: Budget,
+    max_depth: usize,    // 木に含める手の数。その先はプレイアウトだけ
+    expand_after: usize, // 根以外のノードは、この回数訪れるまで子を作らずプレイアウトする
+}
+
+impl Mcts {
+    pub fn new(c: f64, budget: Budget) -> Self {
+        Self {
+            c,
+            budget,
+            max_depth: usize::MAX,
+            expand_after: 1,
+        }
+    }
+
+    // 偶然の分岐が多いと深いところは訪問が散るので、浅く打ち切った方が良いことがある
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth.max(1);
+        self
+    }
+
+    // 1回しか訪れないノードの子を作っても、方策より悪い手で評価してしまうだけになりやすい
+    pub fn expand_after(mut self, visits: usize) -> Self {
+        self.expand_after = visits.max(1);
+        self
+    }
+
+    // root は Decision でなければならない。訪問回数が最も多い手を返す
+    pub fn search<G: Game, R: Rollout<G>>(
+        &self,
+        root: &G,
+        rollout: &R,
+        rng: &mut Rng,
+    ) -> SearchResult<G::Action> {
+        assert_eq!(root.phase(), Phase::Decision, "root must be a decision");
+        let timer = self.budget.timer();
+
+        let mut nodes = vec![Node::new(root)];
+        let mut iterations = 0;
+        loop {
+            let done = match (&timer, &self.budget) {
+                (Some(timer), _) => timer.is_over(),
+                (None, &Budget::Iterations(n)) => iterations >= n,
+                _ => unreachable!(),
+            };
+            // 各手を1回は試す
+            let untried = match &nodes[0].kind {
+                Kind::Decision { untried, .. } => !untried.is_empty(),
+                _ => false,
+            };
+            if done && !untried {
+                break;
+            }
+
+            self.iterate(root, &mut nodes, rollout, rng);
+            iterations += 1;
+        }
+
+        let children = match &nodes[0].kind {
+            Kind::Decision { children, .. } => children
+                .iter()
+                .map(|(a, c)| (a.clone(), nodes[*c].visits, nodes[*c].mean()))
+                .collect::<Vec<_>>(),
+            _ => unreachable!(),
+        };
+        let action = children
+            .iter()
+            .max_by(|a, b| (a.1, a.2).partial_cmp(&(b.1, b.2)).unwrap())
+            .map(|c| c.0.clone())
+            .expect("no legal action at root");
+
+        SearchResult {
+            action,
+            children,
+            iterations,
+            nodes: nodes.len(),
+        }
+    }
+
+    fn ucb<G: Game>(&self, node: &Node<G>, ln_parent: f64) -> f64 {
+        node.mean() + self.c * (ln_parent / node.visits as f64).sqrt()
+    }
+
+    // 選択・展開・プレイアウト・逆伝播を1回
+    fn iterate<G: Game, R: Rollout<G>>(
+        &self,
+        root: &G,
+        nodes: &mut Vec<Node<G>>,
+        rollout: &R,
+        rng: &mut Rng,
+    ) {
+        let mut game = root.clone();
+        let mut path = vec![0];
+        let mut node = 0;
+        let mut depth = 0;
+
+        while depth < self.max_depth && (node == 0 || nodes[node].visits >= self.expand_after) {
+            // 既存の子へ進むなら Some、新しく子を作ったら None
+            let next = match &nodes[node].kind {
+                Kind::Terminal => break,
+                Kind::Decision { children, untried } => {
+                    if untried.is_empty() {
+                        let ln = (nodes[node].visits as f64).ln();
+                        let (action, child) = children
+                            .iter()
+                            .max_by(|a, b| {
+                                let ua = self.ucb(&nodes[a.1], ln);
+                                let ub = self.ucb(&nodes[b.1], ln);
+                                ua.partial_cmp(&ub).unwrap()
+                            })
+                            .unwrap();
+                        game.apply(action);
+                        depth += 1;
+                        Some(*child)
+                    } else {
+                        None
+                    }
+                }
+                Kind::Chance { children } => {
+                    let event = game.sample_event(rng);
+                    game.apply_event(&event);
+                    match children.iter().find(|(e, _)| *e == event) {
+                        Some(&(_, child)) => Some(child),
+                        None => {
+                            let child = nodes.len();
+                            if let Kind::Chance { children } = &mut nodes[node].kind {
+                                children.push((event, child));
+                            }
+                            nodes.push(Node::new(&game));
+                            path.push(child);
+                            break;
+                        }
+                    }
+                }
+            };
+
+            match next {
+                Some(child) => {
+                    node = child;
+                    path.push(child);
+                }
+                None => {
+                    let child = nodes.len();
+                    if let Kind::Decision { children, untried } = &mut nodes[node].kind {
+                        let action = untried.pop().unwrap();
+                        game.apply(&action);
+                        children.push((action, child));
+                    }
+                    nodes.push(Node::new(&game));
+                    path.push(child);
+                    break;
+                }
+            }
+        }
+
+        let value = if game.phase() == Phase::Terminal {
+            game.value()
+        } else {
+            rollout.rollout(&game, rng)
+        };
+        for &i in &path {
+            nodes[i].visits += 1;
+            nodes[i].sum += value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 先に硬貨を見てから当てる (必ず当たる) か、当ててから硬貨を投げる (半々) か
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Act {
+        Peek,
+        Blind,
+        Guess(u8),
+    }
+
+    #[derive(Clone, Default)]
+    struct Guess {
+        peek: Option<bool>,
+        coin: Option<u8>,
+        guess: Option<u8>,
+    }
+
+    impl Game for Guess {
+        type Action = Act;
+        type Event = u8;
+
+        fn phase(&self) -> Phase {
+            match (self.peek, self.coin, self.guess) {
+                (None, _, _) => Phase::Decision,
+                (Some(true), None, _) => Phase::Chance,
+                (Some(false), _, None) | (Some(true), Some(_), None) => Phase::Decision,
+                (Some(false), None, Some(_)) => Phase::Chance,
+                _ => Phase::Terminal,
+            }
+        }
+
+        fn actions(&self) -> Vec<Act> {
+            match self.peek {
+                None => vec![Act::Blind, Act::Peek],
+                Some(_) => vec![Act::Guess(0), Act::Guess(1)],
+            }
+        }
+
+        fn apply(&mut self, action: &Act) {
+            match *action {
+                Act::Peek => self.peek = Some(true),
+                Act::Blind => self.peek = Some(false),
+                Act::Guess(g) => self.guess = Some(g),
+            }
+        }
+
+        fn sample_event(&self, rng: &mut Rng) -> u8 {
+            rng.gen_range(0, 2) as u8
+        }
+
+        fn apply_event(&mut self, event: &u8) {
+            self.coin = Some(*event);
+        }
+
+        fn value(&self) -> f64 {
+            (self.coin == self.guess) as u8 as f64
+        }
+    }
+
+    #[test]
+    fn learns_to_decide_after_the_chance_event() {
+        let mut rng = Rng::new(0);
+        let res = Mcts::new(0.5, Budget::Iterations(3000)).search(
+            &Guess::default(),
+            &RandomRollout,
+            &mut rng,
+        );
+        assert_eq!(res.action, Act::Peek);
+        assert_eq!(res.iterations, 3000);
+        assert_eq!(res.children.iter().map(|c| c.1).sum::<usize>(), 3000);
+
+        // 当てずっぽうの方は訪問が少ない
+        let child = |a: Act| *res.children.iter().find(|c| c.0 == a).unwrap();
+        assert!(child(Act::Peek).1 > 5 * child(Act::Blind).1);
+        assert!(child(Act::Peek).2 > 0.9);
+    }
+
+    // 一様なサイコロ (平均 3.5) か、3〜5 のサイコロ (平均 4) を選んで1回振る
+    #[derive(Clone, Default)]
+    struct Dice {
+        die: Option<usize>,
+        roll: Option<usize>,
+    }
+
+    impl Game for Dice {
+        type Action = usize;
+        type Event = usize;
+
+        fn phase(&self) -> Phase {
+            match (self.die, self.roll) {
+                (None, _) => Phase::Decision,
+                (Some(_), None) => Phase::Chance,
+                _ => Phase::Terminal,
+            }
+        }
+
+        fn actions(&self) -> Vec<usize> {
+            vec![0, 1]
+        }
+
+        fn apply(&mut self, action: &usize) {
+            self.die = Some(*action);
+        }
+
+        fn sample_event(&self, rng: &mut Rng) -> usize {
+            match self.die {
+                Some(0) => rng.gen_range(1, 7),
+                _ => rng.gen_range(3, 6),
+            }
+        }
+
+        fn apply_event(&mut self, event: &usize) {
+            self.roll = Some(*event);
+        }
+
+        fn value(&self) -> f64 {
+            self.roll.unwrap() as f64 / 6.0
+        }
+    }
+
+    #[test]
+    fn estimates_the_expectation_over_chance_outcomes() {
+        let mut rng = Rng::new(1);
+        let res = Mcts::new(0.3, Budget::Iterations(20_000)).search(
+            &Dice::default(),
+            &RandomRollout,
+            &mut rng,
+        );
+        assert_eq!(res.action, 1);
+        let mean = |a: usize| res.children.iter().find(|c| c.0 == a).unwrap().2;
+        assert!((mean(0) - 3.5 / 6.0).abs() < 0.05);
+        assert!((mean(1) - 4.0 / 6.0).abs() < 0.02);
+        // 根 + 2つのサイコロ + 出目ごとの終端 (6 + 3)
+        assert_eq!(res.nodes, 1 + 2 + 9);
+    }
+
+    #[test]
+    fn time_budget_still_tries_every_root_action() {
+        let mut rng = Rng::new(2);
+        let res =
+            Mcts::new(1.0, Budget::Time(0)).search(&Guess::default(), &RandomRollout, &mut rng);
+        assert_eq!(res.iterations, 2);
+        assert_eq!(res.children.len(), 2);
+        assert!(res.children.iter().all(|c| c.1 == 1));
+    }
+
+    #[test]
+    fn fake_timer_makes_time_budget_reproducible() {
+        let run = || {
+            let timer = crate::timer::Timer::fake(100, 1.0);
+            Mcts::new(0.5, Budget::Timer(timer))
+                .search(&Guess::default(), &RandomRollout, &mut Rng::new(6))
+                .children
+        };
+
+        let children = run();
+        // 反復ごとに1回時計を読むので 99 回
+        assert_eq!(children.iter().map(|c| c.1).sum::<usize>(), 99);
+        assert_eq!(run(), children);
+    }
+
+    #[test]
+    fn depth_and_expansion_limits_keep_the_tree_small() {
+        // 1手だけなら根と子だけ
+        let res = Mcts::new(0.5, Budget::Iterations(500)).max_depth(1).search(
+            &Guess::default(),
+            &RandomRollout,
+            &mut Rng::new(4),
+        );
+        assert_eq!(res.nodes, 3);
+        assert_eq!(res.iterations, 500);
+
+        // 子は 10 回訪れるまで展開しない
+        let res = Mcts::new(0.5, Budget::Iterations(12))
+            .expand_after(10)
+            .search(&Guess::default(), &RandomRollout, &mut Rng::new(5));
+        let grandchildren = res.nodes - 3;
+        assert!(grandchildren <= 2, "{}", grandchildren);
+    }
+
+    #[test]
+    fn random_rollout_plays_to_the_end() {
+        let mut rng = Rng::new(3);
+        for _ in 0..100 {
+            let v = RandomRollout.rollout(&Guess::default(), &mut rng);
+            assert!(v == 0.0 || v == 1.0);
+        }
+    }
+}
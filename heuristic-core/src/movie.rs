@@ -0,0 +1,297 @@
+// SVG のお絵かきと、そのコマを並べたパラパラ漫画 (HTML)
+// ローカルの可視化用。提出するソルバーからは使わない
+
+#[derive(Clone)]
+pub struct Graphics {
+    screen_w: f64,
+    screen_h: f64,
+
+    data: String,
+
+    sr: f64,
+    sg: f64,
+    sb: f64,
+    sa: f64,
+
+    fr: f64,
+    fg: f64,
+    fb: f64,
+    fa: f64,
+}
+
+impl Graphics {
+    pub fn new() -> Self {
+        Self {
+            screen_w: 1.0,
+            screen_h: 1.0,
+            data: String::from(""),
+            sr: 0.0,
+            sg: 0.0,
+            sb: 0.0,
+            sa: 0.0,
+            fr: 1.0,
+            fg: 1.0,
+            fb: 1.0,
+            fa: 1.0,
+        }
+    }
+
+    pub fn screen(&mut self, width: f64, height: f64) {
+        self.screen_w = width;
+        self.screen_h = height;
+    }
+
+    pub fn clear(&mut self) {
+        self.data = String::from("");
+    }
+
+    pub fn stroke_rgb(&mut self, r: f64, g: f64, b: f64) {
+        self.stroke_rgba(r, g, b, 1.0)
+    }
+
+    pub fn stroke_rgba(&mut self, r: f64, g: f64, b: f64, a: f64) {
+        self.sr = r;
+        self.sg = g;
+        self.sb = b;
+        self.sa = a;
+    }
+
+    pub fn no_stroke(&mut self) {
+        self.stroke_rgba(0.0, 0.0, 0.0, 0.0)
+    }
+
+    pub fn fill_rgb(&mut self, r: f64, g: f64, b: f64) {
+        self.fill_rgba(r, g, b, 1.0)
+    }
+
+    pub fn fill_rgba(&mut self, r: f64, g: f64, b: f64, a: f64) {
+        self.fr = r;
+        self.fg = g;
+        self.fb = b;
+        self.fa = a;
+    }
+
+    pub fn no_fill(&mut self) {
+        self.fill_rgba(0.0, 0.0, 0.0, 0.0)
+    }
+
+    pub fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) {
+        self.data += &format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" {}/>\n",
+            x1,
+            y1,
+            x2,
+            y2,
+            self.stroke()
+        );
+    }
+
+    pub fn rect(&mut self, x: f64, y: f64, w: f64, h: f64) {
+        self.data += &format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" {} {}/>\n",
+            x,
+            y,
+            w,
+            h,
+            self.stroke(),
+            self.fill()
+        );
+    }
+
+    pub fn polygon(&mut self, points: &[(f64, f64)]) {
+        let points = points
+            .iter()
+            .map(|(x, y)| format!("{},{}", x, y))
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.data += &format!(
+            "<polygon points=\"{}\" {} {}/>\n",
+            points,
+            self.stroke(),
+            self.fill()
+        );
+    }
+
+    pub fn text(&mut self, str: String, x: f64, y: f64, size: f64) {
+        self.data += &format!(
+            "<text text-anchor=\"middle\" x=\"{}\" y=\"{}\" font-size=\"{}\" {} >{}</text>\n",
+            x,
+            y,
+            size,
+            self.fill(),
+            str
+        );
+    }
+
+    pub fn dump(&self, id: Option<String>, style: Option<String>) -> String {
+        let mut res = String::from("<svg ");
+        if let Some(id) = id {
+            res += &format!("id=\"{}\" ", id);
+        };
+        if let Some(style) = style {
+            res += &format!("style=\"{}\" ", style);
+        };
+        res += &format!(
+            "width=\"{}\" viewBox=\"-1 -1 {} {}\" xmlns=\"http://www.w3.org/2000/svg\">\n{}</svg>",
+            self.screen_w + 2.0,
+            self.screen_w + 2.0,
+            self.screen_h + 2.0,
+            self.data
+        );
+
+        res
+    }
+
+    fn stroke(&self) -> String {
+        format!(
+            "stroke=\"{}\" stroke-opacity=\"{}\"",
+            Self::rgb(self.sr, self.sg, self.sb),
+            self.sa
+        )
+    }
+
+    fn fill(&self) -> String {
+        format!(
+            "fill=\"{}\" fill-opacity=\"{}\"",
+            Self::rgb(self.fr, self.fg, self.fb),
+            self.fa
+        )
+    }
+
+    fn rgb(r: f64, g: f64, b: f64) -> String {
+        format!(
+            "rgb({},{},{})",
+            (r * 255.0).round(),
+            (g * 255.0).round(),
+            (b * 255.0).round()
+        )
+    }
+}
+
+pub struct Movie {
+    svgs: Vec<String>,
+}
+
+impl Movie {
+    pub fn new() -> Self {
+        Self { svgs: vec![] }
+    }
+
+    // 相対パスでも可
+    pub fn add_file(&mut self, file_path: &str) {
+        self.svgs.push(format!(
+            "<img id=\"f{}\" style=\"display:none;pointer-events:none;user-select:none;\" src=\"{}\" width=\"1000\" height=\"1000\">",
+            self.svgs.len(),
+            file_path
+        ))
+    }
+
+    pub fn add_frame(&mut self, g: &Graphics) {
+        self.svgs.push(g.dump(
+            Some(format!("f{}", self.svgs.len())),
+            Some(String::from(
+                "display:none;pointer-events:none;user-select:none;",
+            )),
+        ))
+    }
+
+    // クリックで再生/停止、左右キーでコマ送り
+    pub fn dump_html(&self, fps: usize) -> String {
+        let mut s = String::from("<html><body><div id=\"text\">loading...</div>\n");
+
+        // SVG の挿入
+        for svg in &self.svgs {
+            s += svg;
+        }
+
+        s += &format!(
+            "<script>
+            let numFrames = {}, fps = {};",
+            self.svgs.len(),
+            fps
+        );
+        s += "
+            let text = document.getElementById(\"text\");
+            let frames = [];
+            for (let i = 0; i < numFrames; i++) {
+                let f = document.getElementById(\"f\" + i);
+                frames.push(f);
+                f.style.display = \"none\";
+            }
+            let currentFrame = 0;
+            let playing = true;
+            let show = i => {
+                frames[currentFrame].style.display = \"none\";
+                currentFrame = (i + numFrames) % numFrames;
+                frames[currentFrame].style.display = null;
+                text.innerText = (currentFrame + 1) + \" / \" + numFrames;
+            };
+            show(0);
+            setInterval(() => {
+                if (!playing) return;
+                if (currentFrame + 1 == numFrames) playing = false;
+                else show(currentFrame + 1);
+            }, 1000 / fps);
+            window.onmousedown = e => { if (e.button == 0) playing = !playing; };
+            window.onkeydown = e => {
+                if (e.key == \"ArrowLeft\") { playing = false; show(currentFrame - 1); }
+                if (e.key == \"ArrowRight\") { playing = false; show(currentFrame + 1); }
+            };
+            </script></body></html>\n";
+
+        s
+    }
+}
+
+impl Default for Graphics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for Movie {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shapes_use_the_current_colors() {
+        let mut g = Graphics::new();
+        g.screen(10.0, 20.0);
+        g.stroke_rgb(1.0, 0.0, 0.0);
+        g.fill_rgba(0.0, 0.0, 1.0, 0.5);
+        g.rect(1.0, 2.0, 3.0, 4.0);
+        let svg = g.dump(Some(String::from("x")), None);
+        assert!(svg.starts_with("<svg id=\"x\" "));
+        assert!(svg.contains("viewBox=\"-1 -1 12 22\""));
+        assert!(svg.contains("<rect x=\"1\" y=\"2\" width=\"3\" height=\"4\" stroke=\"rgb(255,0,0)\" stroke-opacity=\"1\" fill=\"rgb(0,0,255)\" fill-opacity=\"0.5\"/>"));
+
+        g.no_stroke();
+        g.polygon(&[(0.0, 0.0), (2.5, 0.0), (0.0, 1.0)]);
+        let svg = g.dump(None, None);
+        assert!(svg.contains("<polygon points=\"0,0 2.5,0 0,1\" stroke=\"rgb(0,0,0)\" stroke-opacity=\"0\" fill=\"rgb(0,0,255)\""));
+
+        g.clear();
+        assert!(!g.dump(None, None).contains("<rect"));
+    }
+
+    #[test]
+    fn movie_numbers_the_frames() {
+        let mut mov = Movie::new();
+        let mut g = Graphics::new();
+        for i in 0..3 {
+            g.clear();
+            g.text(format!("{}", i), 0.0, 0.0, 10.0);
+            mov.add_frame(&g);
+        }
+        let html = mov.dump_html(5);
+        assert!(html.contains("id=\"f2\""));
+        assert!(!html.contains("id=\"f3\""));
+        assert!(html.contains("let numFrames = 3, fps = 5;"));
+    }
+}
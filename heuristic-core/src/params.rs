@@ -0,0 +1,101 @@
+// 実行時に変えられるパラメータ (焼きなましの温度など)
+// ファイル (PARAMS_FILE) と環境変数 (PARAMS) から "名前=値" を読む。両方あれば PARAMS が優先。
+// 指定がなければソースに書いた既定値を使うので、提出時は何もしなくてよい
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Default)]
+pub struct Params {
+    values: HashMap<String, String>,
+}
+
+impl Params {
+    // "a=1,b=2" や、1行に "a = 1" のファイルの中身。# 以降は読まない
+    pub fn parse(s: &str) -> Self {
+        let mut values = HashMap::new();
+        for line in s.lines() {
+            let line = line.split('#').next().unwrap();
+            for entry in line.split(',') {
+                if let Some(eq) = entry.find('=') {
+                    let name = entry[..eq].trim();
+                    if !name.is_empty() {
+                        values.insert(name.to_string(), entry[eq + 1..].trim().to_string());
+                    }
+                }
+            }
+        }
+
+        Self { values }
+    }
+
+    pub fn from_env() -> Self {
+        let mut params = Self::default();
+        if let Ok(path) = std::env::var("PARAMS_FILE") {
+            let s = std::fs::read_to_string(&path)
+                .unwrap_or_else(|e| panic!("cannot read PARAMS_FILE {}: {}", path, e));
+            params.values.extend(Self::parse(&s).values);
+        }
+        if let Ok(s) = std::env::var("PARAMS") {
+            params.values.extend(Self::parse(&s).values);
+        }
+
+        params
+    }
+
+    // 値が読めないときは既定値に黙って戻さず止める (調整のつもりが効いていない、を防ぐ)
+    pub fn get<T: FromStr>(&self, name: &str, default: T) -> T {
+        match self.values.get(name) {
+            Some(v) => v
+                .parse()
+                .unwrap_or_else(|_| panic!("invalid value for {}: {}", name, v)),
+            None => default,
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    // 知らない名前 (打ち間違い) を返す
+    pub fn unknown(&self, known: &[&str]) -> Vec<String> {
+        let mut res = self
+            .values
+            .keys()
+            .filter(|k| !known.contains(&k.as_str()))
+            .cloned()
+            .collect::<Vec<_>>();
+        res.sort();
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_env_style_and_file_style() {
+        let p = Params::parse("start_temp=1e-3, loop_num = 500");
+        assert_eq!(p.get("start_temp", 0.0), 1e-3);
+        assert_eq!(p.get("loop_num", 0usize), 500);
+        assert_eq!(p.get("end_temp", 7.0), 7.0);
+
+        let p = Params::parse("# 温度\nstart_temp = 2.5 # 開始\n\nk=80\n");
+        assert_eq!(p.get("start_temp", 0.0), 2.5);
+        assert_eq!(p.get("k", 100usize), 80);
+        assert!(!p.contains("#"));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid value for k")]
+    fn invalid_value_is_an_error() {
+        Params::parse("k=abc").get("k", 1usize);
+    }
+
+    #[test]
+    fn reports_unknown_names() {
+        let p = Params::parse("k=1,limit_time=3000,kk=2");
+        assert_eq!(p.unknown(&["k", "limit_time"]), vec!["kk".to_string()]);
+    }
+}
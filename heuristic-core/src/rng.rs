@@ -0,0 +1,127 @@
+// 速くてシードで再現できる乱数 (xorshift64*)
+
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // 近いシードから近い系列が出ないよう splitmix64 で混ぜる
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+
+        Self {
+            state: if z == 0 { 1 } else { z },
+        }
+    }
+
+    // 時刻からシードを作る (再現しなくていいとき用)
+    pub fn from_time() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Self::new(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    // [lo, hi) の整数
+    pub fn gen_range(&mut self, lo: usize, hi: usize) -> usize {
+        assert!(lo < hi, "empty range: [{}, {})", lo, hi);
+        lo + ((self.next_u64() as u128 * (hi - lo) as u128) >> 64) as usize
+    }
+    pub fn gen_range_i64(&mut self, lo: i64, hi: i64) -> i64 {
+        assert!(lo < hi, "empty range: [{}, {})", lo, hi);
+        let width = (hi as i128 - lo as i128) as u128;
+        (lo as i128 + ((self.next_u64() as u128 * width) >> 64) as i128) as i64
+    }
+
+    // [0, 1) の実数
+    pub fn gen_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // 確率 p で true
+    pub fn gen_bool(&mut self, p: f64) -> bool {
+        self.gen_f64() < p
+    }
+
+    pub fn shuffle<T>(&mut self, v: &mut [T]) {
+        for i in (1..v.len()).rev() {
+            let j = self.gen_range(0, i + 1);
+            v.swap(i, j);
+        }
+    }
+
+    pub fn choose<'a, T>(&mut self, v: &'a [T]) -> Option<&'a T> {
+        if v.is_empty() {
+            None
+        } else {
+            Some(&v[self.gen_range(0, v.len())])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let mut c = Rng::new(43);
+        let xs = (0..100).map(|_| a.next_u64()).collect::<Vec<_>>();
+        let ys = (0..100).map(|_| b.next_u64()).collect::<Vec<_>>();
+        let zs = (0..100).map(|_| c.next_u64()).collect::<Vec<_>>();
+        assert_eq!(xs, ys);
+        assert_ne!(xs, zs);
+    }
+
+    #[test]
+    fn ranges_are_respected_and_covered() {
+        let mut rng = Rng::new(0);
+        let mut seen = [false; 7];
+        for _ in 0..10_000 {
+            let v = rng.gen_range(3, 10);
+            assert!((3..10).contains(&v));
+            seen[v - 3] = true;
+
+            let w = rng.gen_range_i64(-5, 5);
+            assert!((-5..5).contains(&w));
+
+            let f = rng.gen_f64();
+            assert!((0.0..1.0).contains(&f));
+        }
+        assert!(seen.iter().all(|&b| b));
+    }
+
+    #[test]
+    fn gen_bool_follows_probability() {
+        let mut rng = Rng::new(1);
+        let n = 100_000;
+        let hit = (0..n).filter(|_| rng.gen_bool(0.3)).count();
+        assert!((hit as f64 / n as f64 - 0.3).abs() < 0.01);
+        assert!(!(0..1000).any(|_| rng.gen_bool(0.0)));
+    }
+
+    #[test]
+    fn shuffle_is_a_permutation() {
+        let mut rng = Rng::new(2);
+        let mut v = (0..50).collect::<Vec<_>>();
+        rng.shuffle(&mut v);
+        assert_ne!(v, (0..50).collect::<Vec<_>>());
+        v.sort();
+        assert_eq!(v, (0..50).collect::<Vec<_>>());
+        assert_eq!(rng.choose::<usize>(&[]), None);
+    }
+}
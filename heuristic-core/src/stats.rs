@@ -0,0 +1,325 @@
+// 複数の seed で取ったスコアの集計 (平均の信頼区間、2つの解法の対応のある比較)
+
+use std::fmt;
+use std::fmt::Write;
+
+// 平均と 95% 信頼区間の半幅 (1つしかなければ幅は無限)
+pub fn mean_ci(xs: &[f64]) -> (f64, f64) {
+    if xs.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = xs.len() as f64;
+    let mean = xs.iter().sum::<f64>() / n;
+    if xs.len() == 1 {
+        return (mean, f64::INFINITY);
+    }
+    let var = xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+
+    (mean, 1.96 * (var / n).sqrt())
+}
+
+// 同じ seed での A と B の比較。差は「B が良くなった向き」を正にする
+#[derive(Debug, Clone)]
+pub struct Paired {
+    pub deltas: Vec<f64>, // seed ごとの改善量
+    pub wins: usize,      // B の方が良い seed の数
+    pub losses: usize,
+    pub ties: usize,
+    pub mean_delta: f64,
+    pub mean_relative: f64, // seed ごとの改善量 / |A| の平均 (A が 0 の seed は除く)
+    pub t: f64,             // 対応のある t 検定の統計量
+    pub p_value: f64,       // 両側
+}
+
+impl Paired {
+    pub fn new(a: &[f64], b: &[f64], minimize: bool) -> Self {
+        assert_eq!(a.len(), b.len());
+        let deltas = a
+            .iter()
+            .zip(b)
+            .map(|(&a, &b)| if minimize { a - b } else { b - a })
+            .collect::<Vec<_>>();
+        let wins = deltas.iter().filter(|&&d| d > 0.0).count();
+        let losses = deltas.iter().filter(|&&d| d < 0.0).count();
+
+        let relatives = a
+            .iter()
+            .zip(&deltas)
+            .filter(|(&a, _)| a != 0.0)
+            .map(|(a, d)| d / a.abs())
+            .collect::<Vec<_>>();
+        let mean_relative = if relatives.is_empty() {
+            0.0
+        } else {
+            relatives.iter().sum::<f64>() / relatives.len() as f64
+        };
+
+        let (mean_delta, t, p_value) = paired_t_test(&deltas);
+
+        Self {
+            wins,
+            losses,
+            ties: deltas.len() - wins - losses,
+            deltas,
+            mean_delta,
+            mean_relative,
+            t,
+            p_value,
+        }
+    }
+}
+
+// 1つの seed での A と B のスコア (読めなかった方は None)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeedPair {
+    pub seed: u64,
+    pub a: Option<f64>,
+    pub b: Option<f64>,
+}
+
+impl Paired {
+    // 両方のスコアが読めた seed だけで比べる
+    pub fn from_pairs(pairs: &[SeedPair], minimize: bool) -> Self {
+        let (a, b): (Vec<f64>, Vec<f64>) = pairs.iter().filter_map(|p| Some((p.a?, p.b?))).unzip();
+
+        Self::new(&a, &b, minimize)
+    }
+}
+
+// seed ごとのスコアと差 (B が良くなった向きを正) の表、最後に Paired の集計
+pub fn pair_report(pairs: &[SeedPair], minimize: bool) -> String {
+    let show = |x: Option<f64>| match x {
+        Some(x) => format!("{:.3}", x),
+        None => "-".to_string(),
+    };
+
+    let mut s = String::new();
+    writeln!(
+        s,
+        "{:>6}  {:>14}  {:>14}  {:>12}  {:>8}",
+        "seed", "A", "B", "delta", "rel%"
+    )
+    .unwrap();
+    for p in pairs {
+        let (delta, rel) = match (p.a, p.b) {
+            (Some(a), Some(b)) => {
+                let delta = if minimize { a - b } else { b - a };
+                let rel = if a != 0.0 {
+                    format!("{:+.3}", 100.0 * delta / a.abs())
+                } else {
+                    "-".to_string()
+                };
+                (format!("{:+.3}", delta), rel)
+            }
+            _ => ("-".to_string(), "-".to_string()),
+        };
+        writeln!(
+            s,
+            "{:>6}  {:>14}  {:>14}  {:>12}  {:>8}",
+            p.seed,
+            show(p.a),
+            show(p.b),
+            delta,
+            rel
+        )
+        .unwrap();
+    }
+
+    let failed = pairs
+        .iter()
+        .filter(|p| p.a.is_none() || p.b.is_none())
+        .count();
+    if failed > 0 {
+        writeln!(s, "no score on {} seeds (excluded)", failed).unwrap();
+    }
+    writeln!(s, "{}", Paired::from_pairs(pairs, minimize)).unwrap();
+
+    s
+}
+
+impl fmt::Display for Paired {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "seeds: {}  W/L/T: {}/{}/{}",
+            self.deltas.len(),
+            self.wins,
+            self.losses,
+            self.ties
+        )?;
+        writeln!(
+            f,
+            "mean delta: {:+.3}  mean relative: {:+.3}%",
+            self.mean_delta,
+            100.0 * self.mean_relative
+        )?;
+        write!(f, "paired t: {:.3}  p = {:.4}", self.t, self.p_value)
+    }
+}
+
+// => (差の平均, t, 両側 p 値)
+fn paired_t_test(deltas: &[f64]) -> (f64, f64, f64) {
+    let n = deltas.len();
+    if n == 0 {
+        return (0.0, 0.0, 1.0);
+    }
+    let mean = deltas.iter().sum::<f64>() / n as f64;
+    if n == 1 {
+        return (mean, 0.0, 1.0);
+    }
+    let var = deltas.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+    if var == 0.0 {
+        return if mean == 0.0 {
+            (mean, 0.0, 1.0)
+        } else {
+            (mean, mean.signum() * f64::INFINITY, 0.0)
+        };
+    }
+
+    let t = mean / (var / n as f64).sqrt();
+    let df = (n - 1) as f64;
+
+    (mean, t, student_t_two_sided(t, df))
+}
+
+// 自由度 df の t 分布で |T| >= |t| となる確率
+pub fn student_t_two_sided(t: f64, df: f64) -> f64 {
+    incomplete_beta(df / 2.0, 0.5, df / (df + t * t))
+}
+
+fn ln_gamma(x: f64) -> f64 {
+    // Lanczos 近似 (g = 7)
+    const COEF: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = COEF[1..]
+        .iter()
+        .enumerate()
+        .fold(COEF[0], |acc, (i, c)| acc + c / (x + i as f64 + 1.0));
+
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+// 正則化不完全ベータ関数 I_x(a, b)
+fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // 連分数が速く収束する側で計算する
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..300 {
+        let m = m as f64;
+        for &num in &[
+            m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0)),
+        ] {
+            d = 1.0 + num * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + num / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            h *= d * c;
+        }
+        if (d * c - 1.0).abs() < 1e-15 {
+            break;
+        }
+    }
+
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mean_ci_of_small_samples() {
+        let (mean, ci) = mean_ci(&[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(mean, 2.5);
+        assert!((ci - 1.96 * (5.0f64 / 3.0 / 4.0).sqrt()).abs() < 1e-12);
+        assert_eq!(mean_ci(&[3.0]).1, f64::INFINITY);
+        assert_eq!(mean_ci(&[]), (0.0, 0.0));
+    }
+
+    #[test]
+    fn t_distribution_matches_tables() {
+        // 両側 5% 点: df = 9 で 2.262, df = 30 で 2.042。df = 1 の 1 は 0.5
+        assert!((student_t_two_sided(2.262, 9.0) - 0.05).abs() < 5e-4);
+        assert!((student_t_two_sided(-2.042, 30.0) - 0.05).abs() < 5e-4);
+        assert!((student_t_two_sided(1.0, 1.0) - 0.5).abs() < 1e-9);
+        assert!((student_t_two_sided(0.0, 5.0) - 1.0).abs() < 1e-12);
+        assert!(student_t_two_sided(20.0, 10.0) < 1e-8);
+    }
+
+    #[test]
+    fn paired_counts_and_direction() {
+        let a = [100.0, 200.0, 300.0, 400.0];
+        let b = [110.0, 190.0, 300.0, 440.0];
+        let p = Paired::new(&a, &b, false);
+        assert_eq!((p.wins, p.losses, p.ties), (2, 1, 1));
+        assert_eq!(p.deltas, vec![10.0, -10.0, 0.0, 40.0]);
+        assert_eq!(p.mean_delta, 10.0);
+        assert!((p.mean_relative - (0.1 - 0.05 + 0.0 + 0.1) / 4.0).abs() < 1e-12);
+
+        // 小さい方が良いなら向きが逆になる
+        let p = Paired::new(&a, &b, true);
+        assert_eq!((p.wins, p.losses, p.ties), (1, 2, 1));
+        assert_eq!(p.mean_delta, -10.0);
+    }
+
+    #[test]
+    fn consistent_small_improvement_is_significant() {
+        // 各 seed のばらつきは大きいが、差はいつも 1 前後
+        let a = (0..20).map(|i| (i * 137 % 1000) as f64).collect::<Vec<_>>();
+        let b = a
+            .iter()
+            .enumerate()
+            .map(|(i, x)| x + 1.0 + 0.1 * (i % 3) as f64)
+            .collect::<Vec<_>>();
+        let p = Paired::new(&a, &b, false);
+        assert_eq!(p.wins, 20);
+        assert!(p.p_value < 1e-10);
+
+        let same = Paired::new(&a, &a, false);
+        assert_eq!(same.ties, 20);
+        assert_eq!(same.p_value, 1.0);
+    }
+}
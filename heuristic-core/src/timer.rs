@@ -0,0 +1,204 @@
+// 実行時間の計測
+// 本番は Instant (時刻合わせの影響を受けない)。テストでは時計を読むたびに決まった量だけ進む偽の時計にすると、
+// 時間で打ち切る探索も反復回数だけで決まるので毎回同じ結果になる
+// clone や until で作った Timer は元と同じ時計を読む (偽の時計もどれかが読めば全部進む)
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Instant;
+
+#[derive(Debug, Clone)]
+enum Clock {
+    Real(Instant),
+    Fake { reads: Rc<Cell<u64>>, step: f64 }, // step: 1回読むごとに進む ms
+}
+
+#[derive(Debug, Clone)]
+pub struct Timer {
+    clock: Clock,
+    limit: u128,            // ms
+    margin: u128,           // ms。limit からこれを引いたところで打ち切る
+    interval: usize,        // check で時計を読む間隔 (呼び出し回数)
+    countdown: Cell<usize>, // 次に時計を読むまでの check の回数
+    over: Cell<bool>,
+}
+
+impl Timer {
+    pub fn new(limit: u128) -> Self {
+        Self::with_clock(Clock::Real(Instant::now()), limit)
+    }
+
+    // 時計を読むたびに step ms 進む
+    pub fn fake(limit: u128, step: f64) -> Self {
+        Self::with_clock(
+            Clock::Fake {
+                reads: Rc::new(Cell::new(0)),
+                step,
+            },
+            limit,
+        )
+    }
+
+    fn with_clock(clock: Clock, limit: u128) -> Self {
+        Self {
+            clock,
+            limit,
+            margin: 0,
+            interval: 1,
+            countdown: Cell::new(0),
+            over: Cell::new(false),
+        }
+    }
+
+    // 同じ時計で、開始から limit ms までの Timer (1ターン分の締め切りなど)
+    pub fn until(&self, limit: u128) -> Self {
+        Self::with_clock(self.clock.clone(), limit)
+    }
+
+    pub fn margin(mut self, margin: u128) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn interval(mut self, interval: usize) -> Self {
+        self.interval = interval.max(1);
+        self
+    }
+
+    pub fn elapsed(&self) -> u128 {
+        self.elapsed_us() / 1000
+    }
+
+    // ms 未満の区間を測るとき用
+    pub fn elapsed_us(&self) -> u128 {
+        match &self.clock {
+            Clock::Real(start) => start.elapsed().as_micros(),
+            Clock::Fake { reads, step } => {
+                reads.set(reads.get() + 1);
+                (reads.get() as f64 * step * 1000.0) as u128
+            }
+        }
+    }
+
+    // 余裕を引いた実際の制限
+    pub fn limit(&self) -> u128 {
+        self.limit.saturating_sub(self.margin)
+    }
+
+    pub fn remaining(&self) -> u128 {
+        self.limit().saturating_sub(self.elapsed())
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.elapsed() >= self.limit()
+    }
+
+    // 制限時間に対する経過の割合 ([0, 1] に丸める)
+    pub fn progress(&self) -> f64 {
+        if self.limit() == 0 {
+            return 1.0;
+        }
+        (self.elapsed() as f64 / self.limit() as f64).min(1.0)
+    }
+
+    // 軽い is_over。interval 回に1回だけ時計を読み、一度過ぎたらずっと true
+    pub fn check(&self) -> bool {
+        if self.over.get() {
+            return true;
+        }
+        match self.countdown.get() {
+            0 => {
+                self.countdown.set(self.interval - 1);
+                self.over.set(self.is_over());
+            }
+            n => self.countdown.set(n - 1),
+        }
+
+        self.over.get()
+    }
+}
+
+// 探索の打ち切り方
+#[derive(Debug, Clone)]
+pub enum Budget {
+    Time(u128),   // 呼び出してからの ms
+    Timer(Timer), // 渡した Timer の制限まで (偽の時計も使える)
+    Iterations(usize),
+}
+
+impl Budget {
+    // 時間で打ち切るなら、そのための Timer
+    pub fn timer(&self) -> Option<Timer> {
+        match self {
+            Budget::Time(ms) => Some(Timer::new(*ms)),
+            Budget::Timer(timer) => Some(timer.clone()),
+            Budget::Iterations(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_limit_is_over_immediately() {
+        let timer = Timer::new(0);
+        assert!(timer.is_over());
+        assert_eq!(timer.remaining(), 0);
+        assert_eq!(timer.progress(), 1.0);
+    }
+
+    #[test]
+    fn long_limit_is_not_over() {
+        let timer = Timer::new(60_000);
+        assert!(!timer.is_over());
+        assert!(timer.progress() < 0.5);
+        assert!(timer.remaining() > 30_000);
+    }
+
+    #[test]
+    fn fake_clock_advances_per_read() {
+        let timer = Timer::fake(10, 2.5);
+        assert_eq!(timer.elapsed(), 2);
+        assert_eq!(timer.elapsed(), 5);
+        assert!(!timer.is_over()); // 7.5
+        assert_eq!(timer.progress(), 1.0); // 10
+        assert!(timer.is_over());
+
+        let timer = Timer::fake(10, 0.25);
+        assert_eq!(timer.elapsed_us(), 250);
+        assert_eq!(timer.elapsed(), 0);
+    }
+
+    #[test]
+    fn margin_shortens_the_limit() {
+        let timer = Timer::fake(100, 10.0).margin(30);
+        assert_eq!(timer.limit(), 70);
+        let reads = (0..).take_while(|_| !timer.is_over()).count();
+        assert_eq!(reads, 6); // 7回目 (70ms) で打ち切り
+        assert_eq!(Timer::new(10).margin(20).limit(), 0);
+    }
+
+    #[test]
+    fn until_shares_the_clock() {
+        let timer = Timer::fake(100, 1.0).margin(10);
+        assert_eq!(timer.elapsed(), 1);
+        let turn = timer.until(3);
+        assert_eq!(turn.limit(), 3);
+        assert!(!turn.is_over()); // 2
+        assert!(turn.is_over()); // 3
+        assert_eq!(timer.elapsed(), 4);
+    }
+
+    #[test]
+    fn check_reads_the_clock_every_interval_calls() {
+        // 1回読むごとに 1ms 進むので、時計を読んだ回数と経過時間が一致する
+        let timer = Timer::fake(3, 1.0).interval(4);
+        let calls = (0..).take_while(|_| !timer.check()).count();
+        // 0, 4 回目は 1ms, 2ms でまだ、8 回目に 3ms で打ち切り
+        assert_eq!(calls, 8);
+        assert!(timer.check());
+        assert_eq!(timer.elapsed(), 4);
+    }
+}